@@ -0,0 +1,38 @@
+//! 形態素解析器ごとの変換速度の比較。
+//!
+//! vibrato の辞書は同梱していないので、展開済みの IPADIC の辞書 (system.dic) のパスを `VIBRATO_DICT` に指定して
+//! `cargo bench --features vibrato` で実行する。`VIBRATO_DICT` がなければ vibrato の計測は飛ばす。
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use std::fs::File;
+use to_polite::to_polite_sentence;
+use typed_igo::Parser;
+
+const LONGLONG: &str = include_str!("longlong.txt");
+
+fn longlong(c: &mut Criterion) {
+    let vibrato_dict = std::env::var_os("VIBRATO_DICT");
+
+    let parser = Parser::new();
+    c.bench_function("longlong/typed-igo", |b| {
+        b.iter(|| to_polite_sentence(&parser, LONGLONG))
+    });
+
+    let path = match vibrato_dict {
+        Some(path) => path,
+        None => {
+            eprintln!("VIBRATO_DICT is not set; skipping longlong/vibrato");
+            return;
+        }
+    };
+    let dictionary =
+        vibrato::Dictionary::read(File::open(path).expect("failed to open dictionary"))
+            .expect("failed to read dictionary");
+    let tokenizer = vibrato::Tokenizer::new(dictionary);
+    c.bench_function("longlong/vibrato", |b| {
+        b.iter(|| to_polite_sentence(&tokenizer, LONGLONG))
+    });
+}
+
+criterion_group!(benches, longlong);
+criterion_main!(benches);
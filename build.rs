@@ -0,0 +1,18 @@
+fn main() {
+    #[cfg(feature = "capi")]
+    generate_header();
+}
+
+/// C API のヘッダを `OUT_DIR` に生成する。ソースツリーには書き込まない。チェックインしている
+/// `include/to_polite.h` と同じであることは `capi` のテストで確かめる。
+#[cfg(feature = "capi")]
+fn generate_header() {
+    let crate_dir = std::env::var("CARGO_MANIFEST_DIR").expect("CARGO_MANIFEST_DIR is not set");
+    let out_dir = std::env::var("OUT_DIR").expect("OUT_DIR is not set");
+    println!("cargo:rerun-if-changed=src/capi.rs");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+
+    cbindgen::generate(&crate_dir)
+        .expect("failed to generate C header")
+        .write_to_file(std::path::Path::new(&out_dir).join("to_polite.h"));
+}
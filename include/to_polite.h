@@ -0,0 +1,96 @@
+#ifndef TO_POLITE_H
+#define TO_POLITE_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// 状態コード。
+typedef enum ToPoliteStatus {
+  TO_POLITE_STATUS_OK = 0,
+  // 必要なポインタが NULL だった
+  TO_POLITE_STATUS_NULL_POINTER = 1,
+  // 入力が UTF-8 として正しくない
+  TO_POLITE_STATUS_INVALID_UTF8 = 2,
+  // 入力に NUL 文字が含まれている
+  TO_POLITE_STATUS_INTERIOR_NUL = 3,
+  // `target` が `ToPoliteTarget` のどれでもない
+  TO_POLITE_STATUS_INVALID_TARGET = 4,
+  // 変換中に内部でエラーが起きた
+  TO_POLITE_STATUS_INTERNAL = 5,
+} ToPoliteStatus;
+
+// 変換の向き。`to_polite_convert()` の `target` に渡す。
+typedef enum ToPoliteTarget {
+  TO_POLITE_TARGET_POLITE = 0,
+  TO_POLITE_TARGET_IMPOLITE = 1,
+  TO_POLITE_TARGET_CASUAL = 2,
+  TO_POLITE_TARGET_FORMAL = 3,
+} ToPoliteTarget;
+
+// 変換器。`to_polite_converter_new()` で作り、`to_polite_converter_free()` で解放する。
+typedef struct ToPoliteConverter ToPoliteConverter;
+
+// 話し言葉の話し方。`to_polite_convert_casual()` に渡す。項目は `casual::Persona` と同じ。
+typedef struct ToPolitePersona {
+  // 「〜ている」を「〜てる」にする
+  bool contract_teiru;
+  // 「ではない」を「じゃない」にする
+  bool contract_dewa;
+  // 「か」で終わる疑問文を「〜の？」にする
+  bool soft_question;
+  // 「だ」で終わる文に付ける終助詞の NUL 終端の UTF-8 の文字列。NULL か空文字列なら付けない。
+  const char *copula_particle;
+} ToPolitePersona;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// 変換器を作る。失敗した場合は NULL を返す。
+ToPoliteConverter *to_polite_converter_new(void);
+
+// 変換器を解放する。NULL なら何もしない。
+//
+// # Safety
+//
+// `converter` は `to_polite_converter_new()` が返したもので、まだ解放していないこと。
+void to_polite_converter_free(ToPoliteConverter *converter);
+
+// `input` から `len` バイトの UTF-8 の文字列を変換し、NUL 終端の文字列を `*output` に書き込む。
+// 成功した場合だけ `*output` を書き換える。
+//
+// # Safety
+//
+// `converter` は有効な変換器、`input` は `len` バイト読めるポインタ、`output` は書き込めるポインタであること。
+ToPoliteStatus to_polite_convert(const ToPoliteConverter *converter,
+                                 const uint8_t *input,
+                                 uintptr_t len,
+                                 int target,
+                                 char **output);
+
+// `persona` の話し方で話し言葉に変換する。それ以外は `to_polite_convert()` と同じ。
+//
+// # Safety
+//
+// `to_polite_convert()` と同じ条件に加えて、`persona` は読めるポインタで、`persona->copula_particle` は NULL か
+// NUL 終端の文字列であること。
+ToPoliteStatus to_polite_convert_casual(const ToPoliteConverter *converter,
+                                        const uint8_t *input,
+                                        uintptr_t len,
+                                        const ToPolitePersona *persona,
+                                        char **output);
+
+// `to_polite_convert()` や `to_polite_convert_casual()` が返した文字列を解放する。NULL なら何もしない。
+//
+// # Safety
+//
+// `string` は `to_polite_convert()` が返したもので、まだ解放していないこと。
+void to_polite_string_free(char *string);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* TO_POLITE_H */
@@ -0,0 +1,99 @@
+//! 多数の文書の並列変換。
+//!
+//! 形態素解析器はスレッド間で共有し、文書ごとに並列に変換する。結果は入力と同じ順に並ぶ。変換中に panic した文書は
+//! その文書だけエラーになり、ほかの文書の変換は続ける。
+
+use crate::edit;
+use crate::tokenizer::Tokenizer;
+use crate::Target;
+use rayon::prelude::*;
+use std::error::Error;
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+
+/// 変換に失敗した文書。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchError {
+    /// 入力での位置
+    pub index: usize,
+    pub message: String,
+}
+
+impl fmt::Display for BatchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "failed to convert document #{}: {}",
+            self.index, self.message
+        )
+    }
+}
+
+impl Error for BatchError {}
+
+pub fn to_polite_batch<S: AsRef<str> + Sync>(
+    parser: &(dyn Tokenizer + Sync),
+    docs: &[S],
+) -> Vec<Result<String, BatchError>> {
+    convert_batch(parser, docs, Target::Polite)
+}
+
+pub fn to_impolite_batch<S: AsRef<str> + Sync>(
+    parser: &(dyn Tokenizer + Sync),
+    docs: &[S],
+) -> Vec<Result<String, BatchError>> {
+    convert_batch(parser, docs, Target::Impolite)
+}
+
+pub fn convert_batch<S: AsRef<str> + Sync>(
+    parser: &(dyn Tokenizer + Sync),
+    docs: &[S],
+    target: Target,
+) -> Vec<Result<String, BatchError>> {
+    docs.par_iter()
+        .enumerate()
+        .map(|(index, doc)| {
+            let doc = doc.as_ref();
+            panic::catch_unwind(AssertUnwindSafe(|| {
+                edit::apply(doc, &edit::edits(parser, doc, target))
+            }))
+            .map_err(|payload| BatchError {
+                index,
+                message: panic_message(&*payload),
+            })
+        })
+        .collect()
+}
+
+fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "panicked during conversion".to_string()
+    }
+}
+
+#[cfg(all(test, feature = "igo"))]
+mod tests {
+    use super::*;
+    use crate::test_parser;
+
+    #[test]
+    fn keeps_order() {
+        let docs = ["今日は晴天だ。", "今日は寒かった。", "今日は勉強をしよう。"];
+        let results: Vec<_> = to_polite_batch(test_parser(), &docs)
+            .into_iter()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(
+            results,
+            [
+                "今日は晴天です。",
+                "今日は寒かったです。",
+                "今日は勉強をしましょう。"
+            ]
+        );
+    }
+}
@@ -0,0 +1,57 @@
+//! ですます調とである調の混在を検査し、textlint の `--format json` と同じ形で出力する。
+//!
+//! 使い方: `to-polite-lint [--target polite|impolite|formal] FILE...`
+//!
+//! `--target` を省略すると、ファイルごとに文の数が多い方の文体にそろえる。問題が見つかれば終了コード 1 で終わる。
+
+use std::env;
+use std::fs;
+use std::process;
+use to_polite::textlint;
+use to_polite::Target;
+use typed_igo::Parser;
+
+fn main() {
+    let mut target = None;
+    let mut files = Vec::new();
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--target" => {
+                target = match args.next().as_deref() {
+                    Some("polite") => Some(Target::Polite),
+                    Some("impolite") => Some(Target::Impolite),
+                    Some("formal") => Some(Target::Formal),
+                    _ => usage(),
+                }
+            }
+            _ if arg.starts_with('-') => usage(),
+            _ => files.push(arg),
+        }
+    }
+    if files.is_empty() {
+        usage();
+    }
+
+    let parser = Parser::new();
+    let results: Vec<_> = files
+        .iter()
+        .map(|file| {
+            let text = fs::read_to_string(file).unwrap_or_else(|e| {
+                eprintln!("{}: {}", file, e);
+                process::exit(2);
+            });
+            textlint::lint_result(&parser, file, &text, target)
+        })
+        .collect();
+
+    println!("{}", textlint::to_json(&results));
+    if results.iter().any(|result| !result.messages.is_empty()) {
+        process::exit(1);
+    }
+}
+
+fn usage() -> ! {
+    eprintln!("usage: to-polite-lint [--target polite|impolite|formal] FILE...");
+    process::exit(2);
+}
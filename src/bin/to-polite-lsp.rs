@@ -0,0 +1,16 @@
+//! ですます調とである調の混在を診断する Language Server。標準入出力で通信する。
+
+use lsp_server::Connection;
+use std::error::Error;
+use typed_igo::Parser;
+
+fn main() -> Result<(), Box<dyn Error + Send + Sync>> {
+    let parser = Parser::new();
+    let (connection, io_threads) = Connection::stdio();
+    to_polite::lsp::run(&connection, &parser)?;
+
+    drop(connection);
+    io_threads.join()?;
+
+    Ok(())
+}
@@ -0,0 +1,40 @@
+//! JSON で変換を提供する HTTP サーバー。エンドポイントは `to_polite::server` を参照。
+//!
+//! 使い方: `to-polite-server [--addr 127.0.0.1:8080] [--max-body-size BYTES]`
+
+use std::env;
+use std::process;
+use tiny_http::Server;
+use to_polite::server::{self, Config};
+use typed_igo::Parser;
+
+fn main() {
+    let mut addr = "127.0.0.1:8080".to_string();
+    let mut config = Config::default();
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--addr" => addr = args.next().unwrap_or_else(|| usage()),
+            "--max-body-size" => {
+                config.max_body_size = args
+                    .next()
+                    .and_then(|size| size.parse().ok())
+                    .unwrap_or_else(|| usage())
+            }
+            _ => usage(),
+        }
+    }
+
+    let parser = Parser::new();
+    let server = Server::http(&addr).unwrap_or_else(|e| {
+        eprintln!("{}: {}", addr, e);
+        process::exit(1);
+    });
+    eprintln!("listening on http://{}", addr);
+    server::serve(&server, &parser, &config);
+}
+
+fn usage() -> ! {
+    eprintln!("usage: to-polite-server [--addr ADDR] [--max-body-size BYTES]");
+    process::exit(2);
+}
@@ -0,0 +1,246 @@
+//! ビジネス文書向けの言い換え。
+//!
+//! ですます調に変換したあとで、次の言い換えをする。
+//!
+//! - 決まった言い回し : 形態素の境界で一致したものだけを置き換える (「さん」は「様」にするが「さんま」はそのまま)。
+//! - 「ます」の前の動詞 : 主語が自分か、主語が書かれていなければ自分の動作とみなして謙譲語にする
+//!   (行きます -> 参ります)。「田中さんが行きます」のように主語が自分以外なら変えない。
+//! - 名詞 + 「してください」 : 相手の動作なので尊敬語の「ご〜ください」にする (確認してください -> ご確認ください)。
+
+use crate::edit::{self, Edit};
+use crate::tokenizer::{Morpheme, Postpositional, Symbol, Tokenizer, WordClass};
+use crate::Target;
+
+/// 自分を指す主語。謙譲語にしてよいのはこれが主語の文か、主語のない文だけ。
+const FIRST_PERSON: &[&str] = &[
+    "私",
+    "わたし",
+    "わたくし",
+    "僕",
+    "ぼく",
+    "自分",
+    "我々",
+    "私たち",
+    "わたしたち",
+    "私ども",
+    "弊社",
+    "当社",
+];
+
+/// 時を表す名詞。これに付いた「は」は主語の目印ではない。
+const TIME: &[&str] = &[
+    "今",
+    "今日",
+    "明日",
+    "あした",
+    "あす",
+    "明後日",
+    "昨日",
+    "本日",
+    "今度",
+    "今回",
+    "次回",
+    "午前",
+    "午後",
+    "朝",
+    "昼",
+    "夜",
+    "今晩",
+    "今夜",
+];
+
+/// 言い換えの組。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Preset {
+    /// (元の言い回し, 言い換え)
+    phrases: Vec<(String, String)>,
+    /// (動詞の基本形, 謙譲語の連用形)
+    humble_verbs: Vec<(String, String)>,
+}
+
+impl Preset {
+    /// 何も言い換えない組。
+    pub fn new() -> Preset {
+        Preset::default()
+    }
+
+    /// 組み込みのビジネス文書向けの組。
+    pub fn business() -> Preset {
+        Preset::new()
+            .phrase("すみません", "申し訳ございません")
+            .phrase("わかりました", "承知いたしました")
+            .phrase("分かりました", "承知いたしました")
+            .phrase("できない", "いたしかねます")
+            .phrase("できません", "いたしかねます")
+            .phrase("できませんでした", "いたしかねました")
+            .phrase("さん", "様")
+            .humble_verb("する", "いたし")
+            .humble_verb("言う", "申し")
+            .humble_verb("行く", "参り")
+            .humble_verb("来る", "参り")
+            .humble_verb("いる", "おり")
+            .humble_verb("もらう", "いただき")
+            .humble_verb("見る", "拝見し")
+    }
+
+    /// 言い回しを追加する。複数当てはまる場合は長いものを優先する。
+    pub fn phrase(mut self, from: &str, to: &str) -> Preset {
+        if !from.is_empty() {
+            self.phrases.push((from.to_string(), to.to_string()));
+            self.phrases.sort_by(|(a, _), (b, _)| b.len().cmp(&a.len()));
+        }
+        self
+    }
+
+    /// 「ます」の前で謙譲語にする動詞を追加する。`humble` は「ます」に続く連用形 (例: 「参り」)。主語が自分以外の
+    /// 文では謙譲語にしない。
+    pub fn humble_verb(mut self, basic: &str, humble: &str) -> Preset {
+        self.humble_verbs
+            .push((basic.to_string(), humble.to_string()));
+        self
+    }
+
+    /// ですます調に変換してから言い換える。
+    pub fn convert(&self, parser: &dyn Tokenizer, orig: &str) -> String {
+        let polite = edit::apply(orig, &edit::edits(parser, orig, Target::Polite));
+        edit::apply(&polite, &self.edits(parser, &polite))
+    }
+
+    /// `text` に対する言い換えを置換の列として返す。`text` はですます調になっているものとする。
+    pub fn edits(&self, parser: &dyn Tokenizer, text: &str) -> Vec<Edit> {
+        edit::scan_edits(parser, text, |text, before, morphs| {
+            self.edit_at(text, before, morphs)
+        })
+    }
+
+    /// `morphs` の先頭から始まる言い換えと、それが覆う形態素の数を返す。`before` は `morphs` より前の形態素。
+    fn edit_at(
+        &self,
+        text: &str,
+        before: &[Morpheme],
+        morphs: &[Morpheme],
+    ) -> Option<(usize, Edit)> {
+        if let Some(found) = edit::phrase_at(text, morphs, &self.phrases) {
+            return Some(found);
+        }
+
+        let start = morphs[0].start;
+        let end_of = |m: &Morpheme| m.start + m.surface.len();
+
+        match morphs {
+            // 名詞 + 「して」 + 「ください」
+            [noun, suru, te, kudasai, ..]
+                if noun.wordclass == WordClass::Noun
+                    && suru.basic == "する"
+                    && te.wordclass == WordClass::Postpositional(Postpositional::Conjunction)
+                    && kudasai.basic == "くださる" =>
+            {
+                let replacement = format!("ご{}{}", noun.surface, kudasai.surface);
+                Some((4, Edit::replace(start..end_of(kudasai), &replacement)))
+            }
+
+            // 動詞 + 「ます」
+            [verb, masu, ..]
+                if verb.wordclass == WordClass::Verb
+                    && masu.basic == "ます"
+                    && is_first_person_or_omitted(before) =>
+            {
+                self.humble_verbs
+                    .iter()
+                    .find(|(basic, _)| *basic == verb.basic)
+                    .map(|(_, humble)| (1, Edit::replace(start..end_of(verb), humble)))
+            }
+
+            _ => None,
+        }
+    }
+}
+
+/// 文の中で `before` の後ろに続く動詞の主語が自分か、書かれていないか。
+///
+/// 文の始まりまでさかのぼって最も近い「が」か「は」(接続助詞を除く) を主語の目印とし、その前に続く名詞を主語と
+/// みなす。「会議には」のように前が名詞でないものや、「明日は」のように時を表す名詞に付いた「は」は主語の目印に
+/// しない。
+fn is_first_person_or_omitted(before: &[Morpheme]) -> bool {
+    let sentence_start = before
+        .iter()
+        .rposition(|m| m.wordclass == WordClass::Symbol(Symbol::Period))
+        .map_or(0, |idx| idx + 1);
+    let sentence = &before[sentence_start..];
+
+    for (particle, m) in sentence.iter().enumerate().rev() {
+        let is_marker = match m.wordclass {
+            WordClass::Postpositional(Postpositional::Conjunction) => false,
+            WordClass::Postpositional(_) => m.basic == "が" || m.basic == "は",
+            _ => false,
+        };
+        if !is_marker {
+            continue;
+        }
+
+        let noun_start = sentence[..particle]
+            .iter()
+            .rposition(|m| m.wordclass != WordClass::Noun)
+            .map_or(0, |idx| idx + 1);
+        let subject: String = sentence[noun_start..particle]
+            .iter()
+            .map(|m| m.surface.as_str())
+            .collect();
+
+        if subject.is_empty() || (m.basic == "は" && is_time(&subject)) {
+            continue;
+        }
+        return FIRST_PERSON.contains(&subject.as_str());
+    }
+
+    true
+}
+
+/// 時を表す名詞か。「明日」などの決まったものと、「3時」「月曜日」のように時の単位で終わるもの。
+fn is_time(noun: &str) -> bool {
+    TIME.contains(&noun)
+        || ["日", "時", "週", "月", "年"]
+            .iter()
+            .any(|unit| noun.ends_with(unit))
+}
+
+/// 組み込みのビジネス文書向けの言い換えで変換する。
+pub fn to_business(parser: &dyn Tokenizer, orig: &str) -> String {
+    Preset::business().convert(parser, orig)
+}
+
+#[cfg(all(test, feature = "igo"))]
+mod tests {
+    use super::*;
+    use crate::test_parser;
+
+    #[test]
+    fn business() {
+        let business = |orig| to_business(test_parser(), orig);
+        assert_eq!(
+            business("すみません。資料を確認してください。"),
+            "申し訳ございません。資料をご確認ください。"
+        );
+        assert_eq!(
+            business("わかった。明日行く。"),
+            "承知いたしました。明日参ります。"
+        );
+        assert_eq!(business("それはできない。"), "それはいたしかねます。");
+        assert_eq!(business("田中さんに確認する。"), "田中様に確認いたします。");
+        assert_eq!(business("さんまを食べる。"), "さんまを食べます。");
+        assert_eq!(business("私が行く。"), "私が参ります。");
+        assert_eq!(business("明日は行きます。"), "明日は参ります。");
+        assert_eq!(business("会議には行きます。"), "会議には参ります。");
+    }
+
+    #[test]
+    fn others_as_subject() {
+        let business = |orig| to_business(test_parser(), orig);
+        assert_eq!(business("田中さんが行きます。"), "田中様が行きます。");
+        assert_eq!(business("お客様が来ます。"), "お客様が来ます。");
+        assert_eq!(
+            business("田中さんが来るので、私が行く。"),
+            "田中様が来るので、私が参ります。"
+        );
+    }
+}
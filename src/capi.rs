@@ -0,0 +1,308 @@
+//! C から使うための API。
+//!
+//! ヘッダは `include/to_polite.h` にある。`capi` feature でビルドすると `cbindgen` で `OUT_DIR` に生成し、
+//! チェックインしたものと同じかをテストで確かめる。API を変えたら
+//! `cbindgen --config cbindgen.toml --output include/to_polite.h` で更新すること。関数は panic を
+//! 境界の外に出さず、状態コードを返す。返した文字列は `to_polite_string_free()` で解放すること。
+//!
+//! ライブラリは `cargo rustc --release --lib --features capi --crate-type staticlib` (共有ライブラリなら
+//! `cdylib`) か cargo-c でビルドする。
+
+use crate::casual::Persona;
+use crate::{edit, Options, Target};
+use std::ffi::{CStr, CString};
+use std::os::raw::{c_char, c_int};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use typed_igo::Parser;
+
+/// 変換器。`to_polite_converter_new()` で作り、`to_polite_converter_free()` で解放する。
+pub struct ToPoliteConverter {
+    parser: Parser,
+}
+
+/// 状態コード。
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToPoliteStatus {
+    Ok = 0,
+    /// 必要なポインタが NULL だった
+    NullPointer = 1,
+    /// 入力が UTF-8 として正しくない
+    InvalidUtf8 = 2,
+    /// 入力に NUL 文字が含まれている
+    InteriorNul = 3,
+    /// `target` が `ToPoliteTarget` のどれでもない
+    InvalidTarget = 4,
+    /// 変換中に内部でエラーが起きた
+    Internal = 5,
+}
+
+/// 変換の向き。`to_polite_convert()` の `target` に渡す。
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToPoliteTarget {
+    Polite = 0,
+    Impolite = 1,
+    Casual = 2,
+    Formal = 3,
+}
+
+/// 話し言葉の話し方。`to_polite_convert_casual()` に渡す。項目は `casual::Persona` と同じ。
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct ToPolitePersona {
+    /// 「〜ている」を「〜てる」にする
+    pub contract_teiru: bool,
+    /// 「ではない」を「じゃない」にする
+    pub contract_dewa: bool,
+    /// 「か」で終わる疑問文を「〜の？」にする
+    pub soft_question: bool,
+    /// 「だ」で終わる文に付ける終助詞の NUL 終端の UTF-8 の文字列。NULL か空文字列なら付けない。
+    pub copula_particle: *const c_char,
+}
+
+/// 変換器を作る。失敗した場合は NULL を返す。
+#[no_mangle]
+pub extern "C" fn to_polite_converter_new() -> *mut ToPoliteConverter {
+    match panic::catch_unwind(Parser::new) {
+        Ok(parser) => Box::into_raw(Box::new(ToPoliteConverter { parser })),
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+/// 変換器を解放する。NULL なら何もしない。
+///
+/// # Safety
+///
+/// `converter` は `to_polite_converter_new()` が返したもので、まだ解放していないこと。
+#[no_mangle]
+pub unsafe extern "C" fn to_polite_converter_free(converter: *mut ToPoliteConverter) {
+    if !converter.is_null() {
+        drop(Box::from_raw(converter));
+    }
+}
+
+/// `input` から `len` バイトの UTF-8 の文字列を変換し、NUL 終端の文字列を `*output` に書き込む。
+/// 成功した場合だけ `*output` を書き換える。
+///
+/// # Safety
+///
+/// `converter` は有効な変換器、`input` は `len` バイト読めるポインタ、`output` は書き込めるポインタであること。
+#[no_mangle]
+pub unsafe extern "C" fn to_polite_convert(
+    converter: *const ToPoliteConverter,
+    input: *const u8,
+    len: usize,
+    target: c_int,
+    output: *mut *mut c_char,
+) -> ToPoliteStatus {
+    let target = match target {
+        t if t == ToPoliteTarget::Polite as c_int => Target::Polite,
+        t if t == ToPoliteTarget::Impolite as c_int => Target::Impolite,
+        t if t == ToPoliteTarget::Casual as c_int => Target::Casual,
+        t if t == ToPoliteTarget::Formal as c_int => Target::Formal,
+        _ => return ToPoliteStatus::InvalidTarget,
+    };
+
+    convert_with(converter, input, len, &Options::new(target), output)
+}
+
+/// `persona` の話し方で話し言葉に変換する。それ以外は `to_polite_convert()` と同じ。
+///
+/// # Safety
+///
+/// `to_polite_convert()` と同じ条件に加えて、`persona` は読めるポインタで、`persona->copula_particle` は NULL か
+/// NUL 終端の文字列であること。
+#[no_mangle]
+pub unsafe extern "C" fn to_polite_convert_casual(
+    converter: *const ToPoliteConverter,
+    input: *const u8,
+    len: usize,
+    persona: *const ToPolitePersona,
+    output: *mut *mut c_char,
+) -> ToPoliteStatus {
+    if persona.is_null() {
+        return ToPoliteStatus::NullPointer;
+    }
+
+    let persona = &*persona;
+    let copula_particle = if persona.copula_particle.is_null() {
+        ""
+    } else {
+        match CStr::from_ptr(persona.copula_particle).to_str() {
+            Ok(particle) => particle,
+            Err(_) => return ToPoliteStatus::InvalidUtf8,
+        }
+    };
+    let options = Options::new(Target::Casual).persona(Persona {
+        contract_teiru: persona.contract_teiru,
+        contract_dewa: persona.contract_dewa,
+        copula_particle: copula_particle.to_string(),
+        soft_question: persona.soft_question,
+    });
+
+    convert_with(converter, input, len, &options, output)
+}
+
+unsafe fn convert_with(
+    converter: *const ToPoliteConverter,
+    input: *const u8,
+    len: usize,
+    options: &Options,
+    output: *mut *mut c_char,
+) -> ToPoliteStatus {
+    if converter.is_null() || input.is_null() || output.is_null() {
+        return ToPoliteStatus::NullPointer;
+    }
+
+    let input = match std::str::from_utf8(std::slice::from_raw_parts(input, len)) {
+        Ok(input) => input,
+        Err(_) => return ToPoliteStatus::InvalidUtf8,
+    };
+    if input.contains('\0') {
+        return ToPoliteStatus::InteriorNul;
+    }
+
+    let parser = &(*converter).parser;
+    let converted = match panic::catch_unwind(AssertUnwindSafe(|| {
+        edit::apply(input, &edit::edits_with(parser, input, options))
+    })) {
+        Ok(converted) => converted,
+        Err(_) => return ToPoliteStatus::Internal,
+    };
+
+    match CString::new(converted) {
+        Ok(converted) => {
+            *output = converted.into_raw();
+            ToPoliteStatus::Ok
+        }
+        Err(_) => ToPoliteStatus::InteriorNul,
+    }
+}
+
+/// `to_polite_convert()` や `to_polite_convert_casual()` が返した文字列を解放する。NULL なら何もしない。
+///
+/// # Safety
+///
+/// `string` は `to_polite_convert()` が返したもので、まだ解放していないこと。
+#[no_mangle]
+pub unsafe extern "C" fn to_polite_string_free(string: *mut c_char) {
+    if !string.is_null() {
+        drop(CString::from_raw(string));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CStr;
+
+    fn convert(
+        converter: *const ToPoliteConverter,
+        input: &[u8],
+        target: c_int,
+    ) -> Result<String, ToPoliteStatus> {
+        let mut output = ptr::null_mut();
+        let status = unsafe {
+            to_polite_convert(converter, input.as_ptr(), input.len(), target, &mut output)
+        };
+        if status != ToPoliteStatus::Ok {
+            return Err(status);
+        }
+
+        let converted = unsafe { CStr::from_ptr(output) }
+            .to_str()
+            .unwrap()
+            .to_string();
+        unsafe { to_polite_string_free(output) };
+        Ok(converted)
+    }
+
+    #[test]
+    fn round_trip() {
+        let converter = to_polite_converter_new();
+        assert!(!converter.is_null());
+
+        let polite = ToPoliteTarget::Polite as c_int;
+        let impolite = ToPoliteTarget::Impolite as c_int;
+        assert_eq!(
+            convert(converter, "今日は晴天だ。".as_bytes(), polite),
+            Ok("今日は晴天です。".to_string())
+        );
+        assert_eq!(
+            convert(converter, "今日は晴天です。".as_bytes(), impolite),
+            Ok("今日は晴天だ。".to_string())
+        );
+        assert_eq!(
+            convert(
+                converter,
+                "今日は晴天だ。".as_bytes(),
+                ToPoliteTarget::Formal as c_int
+            ),
+            Ok("今日は晴天でございます。".to_string())
+        );
+
+        assert_eq!(
+            convert(converter, b"\xff", polite),
+            Err(ToPoliteStatus::InvalidUtf8)
+        );
+        assert_eq!(
+            convert(converter, b"a\0b", polite),
+            Err(ToPoliteStatus::InteriorNul)
+        );
+        assert_eq!(
+            convert(converter, b"", 4),
+            Err(ToPoliteStatus::InvalidTarget)
+        );
+        assert_eq!(
+            convert(ptr::null(), b"", polite),
+            Err(ToPoliteStatus::NullPointer)
+        );
+
+        unsafe { to_polite_converter_free(converter) };
+    }
+
+    #[test]
+    fn casual_persona() {
+        let converter = to_polite_converter_new();
+        let particle = CString::new("ね").unwrap();
+        let persona = ToPolitePersona {
+            contract_teiru: true,
+            contract_dewa: true,
+            soft_question: true,
+            copula_particle: particle.as_ptr(),
+        };
+
+        let input = "今日は晴天です。";
+        let mut output = ptr::null_mut();
+        let status = unsafe {
+            to_polite_convert_casual(
+                converter,
+                input.as_ptr(),
+                input.len(),
+                &persona,
+                &mut output,
+            )
+        };
+        assert_eq!(status, ToPoliteStatus::Ok);
+        assert_eq!(
+            unsafe { CStr::from_ptr(output) }.to_str(),
+            Ok("今日は晴天だね。")
+        );
+
+        unsafe {
+            to_polite_string_free(output);
+            to_polite_converter_free(converter);
+        }
+    }
+
+    #[test]
+    fn header_is_up_to_date() {
+        assert_eq!(
+            include_str!(concat!(env!("OUT_DIR"), "/to_polite.h")),
+            include_str!("../include/to_polite.h"),
+            "include/to_polite.h is outdated; regenerate it with cbindgen"
+        );
+    }
+}
@@ -0,0 +1,170 @@
+//! くだけた話し言葉 (タメ口)。
+//!
+//! である調に変換してから、話し言葉の縮約と文末の終助詞を加える。どこまで崩すかは `Persona` で決める。
+//! (例: 勉強をしています。 -> 勉強をしてる。、学生ですか？ -> 学生なの？)
+
+use crate::edit::{self, Edit};
+use crate::tokenizer::{Morpheme, Postpositional, Symbol, Tokenizer, WordClass};
+
+/// 話し方の設定。`serde` フィーチャーでは、省略した項目を既定の値にして読み込める。
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct Persona {
+    /// 「〜ている」を「〜てる」にする
+    pub contract_teiru: bool,
+    /// 「ではない」を「じゃない」にする
+    pub contract_dewa: bool,
+    /// 「だ」で終わる文に付ける終助詞 (例: 「よ」なら「晴天だよ」)。空なら付けない。
+    pub copula_particle: String,
+    /// 「か」で終わる疑問文を「〜の？」にする
+    pub soft_question: bool,
+}
+
+impl Default for Persona {
+    fn default() -> Persona {
+        Persona {
+            contract_teiru: true,
+            contract_dewa: true,
+            copula_particle: "よ".to_string(),
+            soft_question: true,
+        }
+    }
+}
+
+/// `persona` の話し方に変換する。
+pub fn to_casual_sentence(parser: &dyn Tokenizer, orig: &str, persona: &Persona) -> String {
+    casualize(parser, &crate::to_impolite_sentence(parser, orig), persona)
+}
+
+/// である調の `plain` を `persona` の話し方にする。
+pub fn casualize(parser: &dyn Tokenizer, plain: &str, persona: &Persona) -> String {
+    edit::apply(plain, &edits(parser, plain, persona))
+}
+
+fn edits(parser: &dyn Tokenizer, plain: &str, persona: &Persona) -> Vec<Edit> {
+    let morphs = parser.tokenize(plain);
+    let end_of = |m: &Morpheme| m.start + m.surface.len();
+    let mut edits = Vec::new();
+
+    for (idx, m) in morphs.iter().enumerate() {
+        let prev = idx.checked_sub(1).map(|idx| &morphs[idx]);
+        let next = morphs.get(idx + 1);
+
+        match m.wordclass {
+            // 「ている」「でいる」 -> 「てる」「でる」
+            WordClass::Verb
+                if persona.contract_teiru
+                    && m.basic == "いる"
+                    && m.surface.starts_with('い')
+                    && prev.is_some_and(|prev| {
+                        prev.wordclass == WordClass::Postpositional(Postpositional::Conjunction)
+                            && (prev.surface == "て" || prev.surface == "で")
+                    }) =>
+            {
+                edits.push(Edit::replace(m.start..m.start + 'い'.len_utf8(), ""));
+            }
+
+            // 「ではない」 -> 「じゃない」
+            _ if persona.contract_dewa
+                && m.surface == "で"
+                && next.is_some_and(|next| next.surface == "は")
+                && morphs.get(idx + 2).is_some_and(|m| m.basic == "ない") =>
+            {
+                edits.push(Edit::replace(m.start..end_of(&morphs[idx + 1]), "じゃ"));
+            }
+
+            // 文末の「だ」に終助詞を付ける
+            WordClass::AuxiliaryVerb
+                if !persona.copula_particle.is_empty()
+                    && m.basic == "だ"
+                    && m.surface == "だ"
+                    && is_end(next) =>
+            {
+                let end = end_of(m);
+                edits.push(Edit::replace(end..end, &persona.copula_particle));
+            }
+
+            // 疑問の「か」 -> 「の？」 (名詞の後ろは「なの？」)
+            // 「だろうか」「しようか」「のか」はそのままの方が自然なので変えない。
+            WordClass::Postpositional(Postpositional::End)
+            | WordClass::Postpositional(Postpositional::SupplementaryParallelEnd)
+                if persona.soft_question
+                    && m.surface == "か"
+                    && is_end(next)
+                    && !prev.is_some_and(|prev| {
+                        (prev.wordclass == WordClass::AuxiliaryVerb && prev.basic == "う")
+                            || prev.surface == "の"
+                    }) =>
+            {
+                let after_noun = prev.is_some_and(|prev| prev.wordclass == WordClass::Noun);
+                let ending = if after_noun { "なの" } else { "の" };
+                match next {
+                    Some(next) if next.surface == "。" => edits.push(Edit::replace(
+                        m.start..end_of(next),
+                        &format!("{}？", ending),
+                    )),
+                    _ => edits.push(Edit::replace(m.start..end_of(m), ending)),
+                }
+            }
+
+            _ => {}
+        }
+    }
+
+    edits
+}
+
+/// 文の終わりか。`next` は直後の形態素。文末の句点や感嘆符、疑問符と入力の終わりだけを文の終わりとし、読点や
+/// 括弧は含めない。
+fn is_end(next: Option<&Morpheme>) -> bool {
+    match next {
+        None => true,
+        Some(next) => match next.wordclass {
+            WordClass::Symbol(Symbol::Period) => true,
+            WordClass::Symbol(_) => ["。", "！", "？", "!", "?"].contains(&next.surface.as_str()),
+            _ => false,
+        },
+    }
+}
+
+#[cfg(all(test, feature = "igo"))]
+mod tests {
+    use super::*;
+    use crate::test_parser;
+
+    #[test]
+    fn casual() {
+        let casual = |orig| to_casual_sentence(test_parser(), orig, &Persona::default());
+        assert_eq!(casual("今日は晴天です。"), "今日は晴天だよ。");
+        assert_eq!(casual("勉強をしています。"), "勉強をしてる。");
+        assert_eq!(casual("彼は学生ではありません。"), "彼は学生じゃない。");
+        assert_eq!(casual("あなたは学生ですか？"), "あなたは学生なの？");
+        assert_eq!(casual("明日は行きますか。"), "明日は行くの？");
+
+        // 文の途中や、疑問の「か」を変えない方が自然なもの
+        assert_eq!(
+            casual("行くか、行かないか迷っています。"),
+            "行くか、行かないか迷ってる。"
+        );
+        assert_eq!(casual("「晴天だ」と言いました。"), "「晴天だ」と言った。");
+        assert_eq!(casual("明日は雨でしょうか。"), "明日は雨だろうか。");
+
+        let persona = Persona {
+            copula_particle: "ね".to_string(),
+            contract_teiru: false,
+            ..Persona::default()
+        };
+        assert_eq!(
+            to_casual_sentence(
+                test_parser(),
+                "今日は晴天です。勉強をしています。",
+                &persona
+            ),
+            "今日は晴天だね。勉強をしている。"
+        );
+    }
+}
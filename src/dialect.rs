@@ -0,0 +1,224 @@
+//! 方言の共通語への置き換え。
+//!
+//! 形態素解析器の辞書は共通語なので、方言の文末はそのままでは変換できない (「休みやねん」に「です」を付けて
+//! しまう)。変換の前に表に従って共通語に置き換える。表には四種類ある。
+//!
+//! - 語 : どこにあっても置き換える (あかん -> だめだ)。
+//! - 文末 : 後ろが文の終わり、記号、終助詞、接続助詞のときだけ置き換える (休みやねん。 -> 休みなのだ。)。
+//! - 文末の助動詞 : 助動詞として解析されていて、文の終わりの直前にあるときだけ置き換える (休みや。 -> 休みだ。)。
+//!   並立助詞の「や」(りんごや、みかん) と区別するため。
+//! - 文末の助詞 : 述語 (動詞、形容詞、助動詞) の直後で、文の終わりの直前にあるときだけ置き換える
+//!   (行くで。 -> 行くよ。)。
+//!
+//! どちらも形態素の境界で一致したものだけを置き換えるので、「部屋」の「や」などは置き換えない。
+
+use crate::edit::{self, Edit};
+use crate::tokenizer::{Morpheme, Postpositional, Symbol, Tokenizer, WordClass};
+use crate::Target;
+
+/// 方言の置き換え表。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Dialect {
+    /// (方言, 共通語)
+    words: Vec<(String, String)>,
+    /// (方言, 共通語)
+    endings: Vec<(String, String)>,
+    /// (方言, 共通語)
+    auxiliaries: Vec<(String, String)>,
+    /// (方言, 共通語)
+    particles: Vec<(String, String)>,
+}
+
+impl Dialect {
+    /// 何も置き換えない表。
+    pub fn new() -> Dialect {
+        Dialect::default()
+    }
+
+    /// 関西弁。
+    pub fn kansai() -> Dialect {
+        Dialect::new()
+            .word("あかんかった", "だめだった")
+            .word("あかん", "だめだ")
+            .word("ほんまに", "本当に")
+            .word("ほんま", "本当")
+            .word("めっちゃ", "とても")
+            .word("おおきに", "ありがとう")
+            .word("せやから", "だから")
+            .word("せやけど", "だけど")
+            .word("なんぼ", "いくら")
+            .ending("やねん", "なのだ")
+            .ending("やんか", "じゃないか")
+            .ending("やった", "だった")
+            .ending("やろ", "だろう")
+            .ending("やで", "だよ")
+            .ending("ねん", "のだ")
+            .ending("せえへん", "しない")
+            .ending("せーへん", "しない")
+            .ending("けえへん", "来ない")
+            .ending("こーへん", "来ない")
+            .ending("へんかった", "なかった")
+            .ending("へん", "ない")
+            .ending("ひん", "ない")
+            .auxiliary("や", "だ")
+            .particle("さかい", "から")
+            .particle("がな", "じゃないか")
+            .particle("で", "よ")
+    }
+
+    /// どこにあっても置き換える語を追加する。複数当てはまる場合は長いものを優先する。
+    pub fn word(mut self, from: &str, to: &str) -> Dialect {
+        push_longest_first(&mut self.words, from, to);
+        self
+    }
+
+    /// 文末でだけ置き換える語を追加する。複数当てはまる場合は長いものを優先する。
+    pub fn ending(mut self, from: &str, to: &str) -> Dialect {
+        push_longest_first(&mut self.endings, from, to);
+        self
+    }
+
+    /// 文末でだけ置き換える助動詞を追加する。複数当てはまる場合は長いものを優先する。
+    pub fn auxiliary(mut self, from: &str, to: &str) -> Dialect {
+        push_longest_first(&mut self.auxiliaries, from, to);
+        self
+    }
+
+    /// 述語の後ろの文末でだけ置き換える助詞を追加する。複数当てはまる場合は長いものを優先する。
+    pub fn particle(mut self, from: &str, to: &str) -> Dialect {
+        push_longest_first(&mut self.particles, from, to);
+        self
+    }
+
+    /// 共通語に置き換える。
+    pub fn normalize(&self, parser: &dyn Tokenizer, orig: &str) -> String {
+        edit::apply(orig, &self.edits(parser, orig))
+    }
+
+    /// 共通語に置き換えてから `target` の文体に変換する。
+    pub fn convert(&self, parser: &dyn Tokenizer, orig: &str, target: Target) -> String {
+        let normalized = self.normalize(parser, orig);
+        edit::apply(&normalized, &edit::edits(parser, &normalized, target))
+    }
+
+    /// 共通語にするための置換の列を返す。
+    pub fn edits(&self, parser: &dyn Tokenizer, orig: &str) -> Vec<Edit> {
+        edit::scan_edits(parser, orig, |orig, before, morphs| {
+            self.edit_at(orig, before, morphs)
+        })
+    }
+
+    /// `morphs` の先頭から始まる置き換えと、それが覆う形態素の数を返す。`before` は `morphs` より前の形態素。
+    fn edit_at(
+        &self,
+        orig: &str,
+        before: &[Morpheme],
+        morphs: &[Morpheme],
+    ) -> Option<(usize, Edit)> {
+        if let Some(found) = edit::phrase_at(orig, morphs, &self.words) {
+            return Some(found);
+        }
+
+        let ending = edit::phrase_at(orig, morphs, &self.endings)
+            .filter(|&(len, _)| is_boundary(morphs.get(len)));
+        if ending.is_some() {
+            return ending;
+        }
+
+        let auxiliary = edit::phrase_at(orig, morphs, &self.auxiliaries).filter(|&(len, _)| {
+            morphs[0].wordclass == WordClass::AuxiliaryVerb && is_sentence_end(morphs.get(len))
+        });
+        if auxiliary.is_some() {
+            return auxiliary;
+        }
+
+        let is_after_predicate = matches!(
+            before.last().map(|m| m.wordclass),
+            Some(WordClass::Verb) | Some(WordClass::Adjective) | Some(WordClass::AuxiliaryVerb)
+        );
+        edit::phrase_at(orig, morphs, &self.particles)
+            .filter(|&(len, _)| is_after_predicate && is_sentence_end(morphs.get(len)))
+    }
+}
+
+/// 文末とみなせる位置か。`next` は直後の形態素。
+fn is_boundary(next: Option<&Morpheme>) -> bool {
+    match next.map(|m| m.wordclass) {
+        None => true,
+        Some(WordClass::Symbol(_)) => true,
+        Some(WordClass::Postpositional(Postpositional::End))
+        | Some(WordClass::Postpositional(Postpositional::SupplementaryParallelEnd))
+        | Some(WordClass::Postpositional(Postpositional::Conjunction)) => true,
+        Some(_) => false,
+    }
+}
+
+/// 文の終わりの直前か。`next` は直後の形態素。読点などの記号は文の終わりとみなさない。
+fn is_sentence_end(next: Option<&Morpheme>) -> bool {
+    match next {
+        None => true,
+        Some(m) => match m.wordclass {
+            WordClass::Symbol(Symbol::Period) => true,
+            WordClass::Symbol(_) => ["！", "？", "!", "?"].contains(&m.surface.as_str()),
+            WordClass::Postpositional(Postpositional::End)
+            | WordClass::Postpositional(Postpositional::SupplementaryParallelEnd) => true,
+            _ => false,
+        },
+    }
+}
+
+fn push_longest_first(table: &mut Vec<(String, String)>, from: &str, to: &str) {
+    if !from.is_empty() {
+        table.push((from.to_string(), to.to_string()));
+        table.sort_by(|(a, _), (b, _)| b.len().cmp(&a.len()));
+    }
+}
+
+#[cfg(all(test, feature = "igo"))]
+mod tests {
+    use super::*;
+    use crate::test_parser;
+
+    #[test]
+    fn kansai() {
+        let kansai = Dialect::kansai();
+        let normalize = |orig| kansai.normalize(test_parser(), orig);
+        assert_eq!(normalize("今日は休みやねん。"), "今日は休みなのだ。");
+        assert_eq!(normalize("明日は行かへん。"), "明日は行かない。");
+        assert_eq!(normalize("それはあかん。"), "それはだめだ。");
+        assert_eq!(normalize("部屋を掃除した。"), "部屋を掃除した。");
+        assert_eq!(normalize("明日は雨や。"), "明日は雨だ。");
+        assert_eq!(
+            normalize("りんごや、みかんを買った。"),
+            "りんごや、みかんを買った。"
+        );
+
+        assert_eq!(
+            kansai.convert(
+                test_parser(),
+                "明日は行かへん。それはあかん。",
+                Target::Polite
+            ),
+            "明日は行きません。それはだめです。"
+        );
+    }
+
+    #[test]
+    fn particles() {
+        let kansai = Dialect::kansai();
+        let normalize = |orig| kansai.normalize(test_parser(), orig);
+        assert_eq!(normalize("もう行くで。"), "もう行くよ。");
+        assert_eq!(normalize("そこにあるがな。"), "そこにあるじゃないか。");
+        assert_eq!(normalize("もう帰るさかい。"), "もう帰るから。");
+        assert_eq!(normalize("駅で待つ。"), "駅で待つ。");
+    }
+
+    #[test]
+    fn custom_table() {
+        let dialect = Dialect::new().ending("だべ", "だろう");
+        assert_eq!(
+            dialect.normalize(test_parser(), "明日は雨だべ。"),
+            "明日は雨だろう。"
+        );
+    }
+}
@@ -0,0 +1,170 @@
+//! ユーザー辞書。
+//!
+//! 製品名や社名などの固有名詞は、形態素解析器の辞書になければ動詞などに誤って分割され、おかしな活用をされる
+//! ことがある。ユーザー辞書に登録した語は一つの名詞として扱い、変換で書き換えない。
+
+use crate::tokenizer::{
+    Conjugation, ConjugationForm, ConjugationKind, Morpheme, Tokenizer, WordClass,
+};
+use std::ops::Range;
+
+/// ユーザー辞書の語。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub surface: String,
+    /// 品詞 (例: 「固有名詞」)。記録用で、変換では常に名詞として扱う。
+    pub pos: String,
+    /// 読み (カタカナ)
+    pub reading: String,
+}
+
+impl Entry {
+    pub fn new(surface: &str, pos: &str, reading: &str) -> Entry {
+        Entry {
+            surface: surface.to_string(),
+            pos: pos.to_string(),
+            reading: reading.to_string(),
+        }
+    }
+}
+
+/// 登録した語を先に切り出し、残りを `tokenizer` で解析する形態素解析器。
+///
+/// ```ignore
+/// let tokenizer = UserDictionary::new(&parser)
+///     .add(Entry::new("ホゲクラウド", "固有名詞", "ホゲクラウド"))
+///     .add(Entry::new("ピヨ株式会社", "固有名詞", "ピヨカブシキガイシャ"));
+/// to_polite_sentence(&tokenizer, "ホゲクラウドを使う。");
+/// ```
+pub struct UserDictionary<T> {
+    tokenizer: T,
+    entries: Vec<Entry>,
+}
+
+impl<T: Tokenizer> UserDictionary<T> {
+    pub fn new(tokenizer: T) -> UserDictionary<T> {
+        UserDictionary {
+            tokenizer,
+            entries: Vec::new(),
+        }
+    }
+
+    pub fn add(mut self, entry: Entry) -> UserDictionary<T> {
+        if !entry.surface.is_empty() {
+            self.entries.push(entry);
+        }
+        self
+    }
+
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    /// `text` の先頭に一致する語のうち、最も長いもの。
+    fn longest_match(&self, text: &str) -> Option<&Entry> {
+        self.entries
+            .iter()
+            .filter(|entry| text.starts_with(&entry.surface))
+            .max_by_key(|entry| entry.surface.len())
+    }
+
+    /// 登録した語の間を `tokenizer` で解析する。
+    fn tokenize_gap(&self, text: &str, range: Range<usize>, morphs: &mut Vec<Morpheme>) {
+        if range.start == range.end {
+            return;
+        }
+
+        morphs.extend(
+            self.tokenizer
+                .tokenize(&text[range.clone()])
+                .into_iter()
+                .map(|m| Morpheme {
+                    start: range.start + m.start,
+                    ..m
+                }),
+        );
+    }
+}
+
+impl<T: Tokenizer> Tokenizer for UserDictionary<T> {
+    fn tokenize(&self, text: &str) -> Vec<Morpheme> {
+        let mut morphs = Vec::new();
+        // まだ解析していない部分の先頭
+        let mut rest_start = 0;
+        let mut pos = 0;
+
+        while pos < text.len() {
+            let entry = match self.longest_match(&text[pos..]) {
+                Some(entry) => entry,
+                None => {
+                    pos += text[pos..].chars().next().map_or(1, char::len_utf8);
+                    continue;
+                }
+            };
+
+            self.tokenize_gap(text, rest_start..pos, &mut morphs);
+            morphs.push(Morpheme {
+                surface: entry.surface.clone(),
+                basic: entry.surface.clone(),
+                reading: entry.reading.clone(),
+                pronunciation: entry.reading.clone(),
+                wordclass: WordClass::Noun,
+                conjugation: Conjugation {
+                    kind: ConjugationKind::None,
+                    form: ConjugationForm::None,
+                },
+                start: pos,
+            });
+            pos += entry.surface.len();
+            rest_start = pos;
+        }
+
+        self.tokenize_gap(text, rest_start..text.len(), &mut morphs);
+
+        morphs
+    }
+}
+
+#[cfg(all(test, feature = "igo"))]
+mod tests {
+    use super::*;
+    use crate::{reading, test_parser, to_impolite_sentence, to_polite_sentence};
+    use typed_igo::Parser;
+
+    fn tokenizer() -> UserDictionary<&'static Parser> {
+        UserDictionary::new(test_parser())
+            .add(Entry::new("ホゲクラウド", "固有名詞", "ホゲクラウド"))
+            .add(Entry::new(
+                "ピヨ株式会社",
+                "固有名詞",
+                "ピヨカブシキガイシャ",
+            ))
+    }
+
+    #[test]
+    fn atomic_nouns() {
+        let tokenizer = tokenizer();
+        let morphs = tokenizer.tokenize("ピヨ株式会社のホゲクラウドを使う。");
+        assert_eq!(morphs[0].surface, "ピヨ株式会社");
+        assert_eq!(morphs[0].wordclass, WordClass::Noun);
+        assert_eq!(morphs[2].surface, "ホゲクラウド");
+        assert_eq!(morphs[2].start, "ピヨ株式会社の".len());
+
+        assert_eq!(
+            to_polite_sentence(&tokenizer, "ピヨ株式会社のホゲクラウドを使う。"),
+            "ピヨ株式会社のホゲクラウドを使います。"
+        );
+        assert_eq!(
+            to_polite_sentence(&tokenizer, "製品名はホゲクラウド。"),
+            "製品名はホゲクラウドです。"
+        );
+        assert_eq!(
+            to_impolite_sentence(&tokenizer, "製品名はホゲクラウドです。"),
+            "製品名はホゲクラウドだ。"
+        );
+        assert_eq!(
+            reading::read(&tokenizer, "ピヨ株式会社").0,
+            "ピヨカブシキガイシャ"
+        );
+    }
+}
@@ -0,0 +1,370 @@
+//! 変換結果を元の文字列に対する置換 (`Edit`) として扱うためのもの。
+//!
+//! 文ごとに変換して、変わった部分だけを置換として取り出す。置換は元の文字列のバイト位置で表すので、
+//! マークアップなどで分断された文字列にも書き戻せる。
+
+use crate::tokenizer::{Morpheme, Tokenizer};
+use crate::{convert, convert_sentences, Options, Target};
+use std::ops::Range;
+
+/// 元の文字列の `range` を `replacement` で置き換える。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Edit {
+    pub range: Range<usize>,
+    pub replacement: String,
+}
+
+impl Edit {
+    /// `range` を `replacement` で置き換える。
+    pub fn replace(range: Range<usize>, replacement: &str) -> Edit {
+        Edit {
+            range,
+            replacement: replacement.to_string(),
+        }
+    }
+}
+
+/// 文の範囲 (バイト位置) を返す。前後の空白は含まない。
+///
+/// 句点などの文末記号か改行で区切る。ただし `Splitter` と同じく括弧の中では区切らない。
+pub fn sentence_ranges(orig: &str) -> Vec<Range<usize>> {
+    let mut scanner = SentenceScanner::default();
+    let mut ranges: Vec<_> = std::iter::from_fn(|| scanner.next(orig)).collect();
+    ranges.extend(scanner.finish(orig));
+
+    ranges
+}
+
+/// `sentence_ranges()` の途中の状態。後ろに伸びていく文字列を、走査済みの部分を読み直さずに区切るためのもの。
+#[derive(Debug, Default)]
+pub(crate) struct SentenceScanner {
+    /// 走査済みの位置
+    pos: usize,
+    /// 区切りがまだ見つかっていない文の開始位置
+    start: Option<usize>,
+    paren_level: u32,
+}
+
+impl SentenceScanner {
+    /// 走査済みの位置から先を走査し、次に区切りが見つかった文の範囲を返す。区切りがなければ `None` 。
+    pub(crate) fn next(&mut self, text: &str) -> Option<Range<usize>> {
+        while let Some(ch) = text[self.pos..].chars().next() {
+            let idx = self.pos;
+            self.pos += ch.len_utf8();
+
+            if ch == '\n' {
+                self.paren_level = 0;
+                match self.start.take() {
+                    Some(start) => return trimmed_range(text, start, idx),
+                    None => continue,
+                }
+            }
+
+            if self.start.is_none() {
+                if ch.is_whitespace() {
+                    continue;
+                }
+                self.start = Some(idx);
+            }
+
+            match ch {
+                '「' | '『' | '（' | '(' | '【' | '［' | '[' | '〈' | '《' => {
+                    self.paren_level += 1
+                }
+                '」' | '』' | '）' | ')' | '】' | '］' | ']' | '〉' | '》' => {
+                    self.paren_level = self.paren_level.saturating_sub(1)
+                }
+                '。' | '．' | '！' | '？' if self.paren_level == 0 => {
+                    if let Some(start) = self.start.take() {
+                        return trimmed_range(text, start, self.pos);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        None
+    }
+
+    /// 区切りのないまま `text` の末尾まで続いた文の範囲を返す。`next()` が `None` を返してから呼ぶ。
+    pub(crate) fn finish(&mut self, text: &str) -> Option<Range<usize>> {
+        self.pos = text.len();
+        let start = self.start.take()?;
+        trimmed_range(text, start, text.len())
+    }
+
+    /// 文字列の先頭から `len` バイトが取り除かれたので、位置をずらす。
+    pub(crate) fn drain(&mut self, len: usize) {
+        self.pos = self.pos.saturating_sub(len);
+        self.start = self.start.map(|start| start.saturating_sub(len));
+    }
+}
+
+/// `text[start..end]` から末尾の空白を除いた範囲。空なら `None` 。
+fn trimmed_range(text: &str, start: usize, end: usize) -> Option<Range<usize>> {
+    let trimmed = text[start..end].trim_end();
+    if trimmed.is_empty() {
+        None
+    } else {
+        Some(start..start + trimmed.len())
+    }
+}
+
+/// 文ごとに変換し、変わった部分を置換の列として返す。置換は位置の昇順で重ならない。
+pub fn edits(parser: &dyn Tokenizer, orig: &str, target: Target) -> Vec<Edit> {
+    sentence_ranges(orig)
+        .into_iter()
+        .filter_map(|range| {
+            let sentence = &orig[range.clone()];
+            let converted = convert(parser, sentence, target);
+            minimal_edit(sentence, &converted).map(|edit| Edit {
+                range: range.start + edit.range.start..range.start + edit.range.end,
+                replacement: edit.replacement,
+            })
+        })
+        .collect()
+}
+
+/// `options` に従って文ごとに変換し、変わった部分を置換の列として返す。確信度が足りない文は変換しない。
+pub fn edits_with(parser: &dyn Tokenizer, orig: &str, options: &Options) -> Vec<Edit> {
+    convert_sentences(parser, orig, options)
+        .into_iter()
+        .filter_map(|result| {
+            minimal_edit(&result.original, &result.converted).map(|edit| Edit {
+                range: result.range.start + edit.range.start..result.range.start + edit.range.end,
+                replacement: edit.replacement,
+            })
+        })
+        .collect()
+}
+
+/// 置換を適用した文字列を返す。
+pub fn apply(orig: &str, edits: &[Edit]) -> String {
+    let mut result = String::with_capacity(orig.len());
+    let mut pos = 0;
+    for edit in edits {
+        result.push_str(&orig[pos..edit.range.start]);
+        result.push_str(&edit.replacement);
+        pos = edit.range.end;
+    }
+    result.push_str(&orig[pos..]);
+
+    result
+}
+
+/// `orig` を `converted` にする最小の (共通の先頭と末尾を除いた) 置換を返す。同じなら `None` 。
+pub(crate) fn minimal_edit(orig: &str, converted: &str) -> Option<Edit> {
+    if orig == converted {
+        return None;
+    }
+
+    let prefix: usize = orig
+        .chars()
+        .zip(converted.chars())
+        .take_while(|(a, b)| a == b)
+        .map(|(a, _)| a.len_utf8())
+        .sum();
+    let suffix: usize = orig[prefix..]
+        .chars()
+        .rev()
+        .zip(converted[prefix..].chars().rev())
+        .take_while(|(a, b)| a == b)
+        .map(|(a, _)| a.len_utf8())
+        .sum();
+
+    Some(Edit {
+        range: prefix..orig.len() - suffix,
+        replacement: converted[prefix..converted.len() - suffix].to_string(),
+    })
+}
+
+/// `morphs` の先頭の形態素から始まり、形態素の境界で終わる言い回しを `phrases` (元の言い回し, 言い換え) から
+/// 探す。見つかれば、言い回しが覆う形態素の数と置換を返す。`phrases` は長いものから順に並べておく。
+///
+/// `morphs` は `text` を解析したものの一部。
+pub(crate) fn phrase_at(
+    text: &str,
+    morphs: &[Morpheme],
+    phrases: &[(String, String)],
+) -> Option<(usize, Edit)> {
+    let start = morphs.first()?.start;
+    phrases.iter().find_map(|(from, to)| {
+        if !text[start..].starts_with(from.as_str()) {
+            return None;
+        }
+        let end = start + from.len();
+        let len = morphs
+            .iter()
+            .position(|m| m.start + m.surface.len() == end)?;
+        Some((
+            len + 1,
+            Edit {
+                range: start..end,
+                replacement: to.clone(),
+            },
+        ))
+    })
+}
+
+/// `text` を解析し、先頭の形態素から順に `edit_at` で置換を探して返す。
+///
+/// `edit_at` は `text` と、それまでの形態素、そこから始まる形態素を受け取り、置換とそれが覆う形態素の数を返す。
+/// 置換が見つかれば覆う形態素の分だけ、見つからなければ一つ進む。
+pub(crate) fn scan_edits<F>(parser: &dyn Tokenizer, text: &str, mut edit_at: F) -> Vec<Edit>
+where
+    F: FnMut(&str, &[Morpheme], &[Morpheme]) -> Option<(usize, Edit)>,
+{
+    let morphs = parser.tokenize(text);
+    let mut edits = Vec::new();
+    let mut idx = 0;
+    while idx < morphs.len() {
+        match edit_at(text, &morphs[..idx], &morphs[idx..]) {
+            Some((len, edit)) => {
+                edits.push(edit);
+                idx += len;
+            }
+            None => idx += 1,
+        }
+    }
+
+    edits
+}
+
+/// `apply_to_runs()` に渡す断片。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Run<'a> {
+    pub text: &'a str,
+    /// 書き換えてはいけない断片 (インラインのコードなど) か
+    pub protected: bool,
+}
+
+/// 連結すると一つの文字列になる断片 (`runs`) に対して置換を適用し、断片ごとの結果を返す。
+///
+/// 置換は開始位置を含む断片に入れる。断片の境界ちょうどから始まる置換は後ろの断片に入る。置換範囲に
+/// 含まれるそれ以降の断片の文字は取り除かれる。
+///
+/// 保護された断片にかかる置換は捨てる。保護された断片の境界への挿入は、その直前で接する保護されていない
+/// 断片に入れ、そのような断片がなければ捨てる。
+pub(crate) fn apply_to_runs(runs: &[Run], edits: &[Edit]) -> Vec<String> {
+    let joined: String = runs.iter().map(|run| run.text).collect();
+    let mut bounds = Vec::with_capacity(runs.len());
+    let mut start = 0;
+    for run in runs {
+        bounds.push(start..start + run.text.len());
+        start += run.text.len();
+    }
+
+    let mut assigned = vec![Vec::new(); runs.len()];
+    for edit in edits {
+        let overlaps = runs.iter().zip(&bounds).any(|(run, bound)| {
+            run.protected && edit.range.start < bound.end && edit.range.end > bound.start
+        });
+        if overlaps {
+            continue;
+        }
+
+        let idx = match bounds.iter().position(|bound| edit.range.start < bound.end) {
+            Some(idx) => idx,
+            None if runs.is_empty() => continue,
+            None => runs.len() - 1,
+        };
+        let idx = if runs[idx].protected {
+            match (0..idx)
+                .rev()
+                .take_while(|&j| bounds[j].end == edit.range.start)
+                .find(|&j| !runs[j].protected)
+            {
+                Some(j) => j,
+                None => continue,
+            }
+        } else {
+            idx
+        };
+        assigned[idx].push(edit);
+    }
+
+    let mut result = Vec::with_capacity(runs.len());
+    let mut pos = 0;
+    for (bound, edits) in bounds.into_iter().zip(assigned) {
+        let mut text = String::new();
+        pos = pos.max(bound.start);
+
+        for edit in edits {
+            if pos < edit.range.start {
+                text.push_str(&joined[pos..edit.range.start]);
+            }
+            text.push_str(&edit.replacement);
+            pos = pos.max(edit.range.end);
+        }
+
+        if pos < bound.end {
+            text.push_str(&joined[pos..bound.end]);
+            pos = bound.end;
+        }
+
+        result.push(text);
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ranges() {
+        let text = "今日は晴天だ。 明日は「雨だ。」と聞いた。\n見出し";
+        let sentences: Vec<_> = sentence_ranges(text)
+            .into_iter()
+            .map(|r| &text[r])
+            .collect();
+        assert_eq!(
+            sentences,
+            ["今日は晴天だ。", "明日は「雨だ。」と聞いた。", "見出し"]
+        );
+    }
+
+    fn plain<'a>(runs: &[&'a str]) -> Vec<Run<'a>> {
+        runs.iter()
+            .map(|&text| Run {
+                text,
+                protected: false,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn runs() {
+        let runs = plain(&["今日は", "晴天", "だ", "。"]);
+        let edit = minimal_edit("今日は晴天だ。", "今日は晴天です。").unwrap();
+        assert_eq!(
+            apply_to_runs(&runs, &[edit]),
+            ["今日は", "晴天", "です", "。"]
+        );
+
+        let runs = plain(&["今日は", "晴天", "。"]);
+        let edit = minimal_edit("今日は晴天。", "今日は晴天です。").unwrap();
+        assert_eq!(apply_to_runs(&runs, &[edit]), ["今日は", "晴天", "です。"]);
+    }
+
+    #[test]
+    fn protected_runs() {
+        let mut runs = plain(&["値は", "x", "だ"]);
+        runs[1].protected = true;
+        let insert = |pos: usize, replacement: &str| Edit::replace(pos..pos, replacement);
+
+        // 保護された断片の先頭への挿入は直前の断片に入る
+        assert_eq!(
+            apply_to_runs(&runs, &[insert("値は".len(), "、")]),
+            ["値は、", "x", "だ"]
+        );
+
+        // 末尾が保護された断片なら文末への挿入は捨てる
+        let runs = &runs[..2];
+        assert_eq!(
+            apply_to_runs(runs, &[insert("値はx".len(), "です。")]),
+            ["値は", "x"]
+        );
+    }
+}
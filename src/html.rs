@@ -0,0 +1,357 @@
+//! HTML 文書の変換。
+//!
+//! テキストノードだけを変換し、タグや属性には手を付けない。`<b>` や `<a>` などのインライン要素で分断された
+//! テキストはつなげて一つの文として変換し、変わった部分を元のテキストノードに書き戻す。`<pre>`, `<script>`,
+//! `<style>`, `<textarea>` の中は変換しない。`<code>` の中身は文の一部として解析はするが書き換えない。
+//! 文字参照 (`&amp;` など) は展開せずにそのまま解析する。
+//!
+//! 見出し (`<h1>` から `<h6>`)、箇条書きの項目 (`<li>`)、表のセル (`<td>`, `<th>`) は Markdown と同じく
+//! `MarkdownPolicy` の方針に従って変換する。
+
+use crate::edit::{self, Run};
+use crate::markdown::{self, BlockKind, MarkdownPolicy};
+use crate::tokenizer::Tokenizer;
+use crate::Target;
+
+/// 文を分断しないインライン要素
+const INLINE: &[&str] = &[
+    "a", "abbr", "b", "bdi", "bdo", "cite", "code", "data", "dfn", "em", "font", "i", "kbd",
+    "mark", "q", "rb", "rp", "rt", "ruby", "s", "samp", "small", "span", "strong", "sub", "sup",
+    "time", "u", "var", "wbr",
+];
+
+/// 中身を一切変換しない要素
+const SKIP: &[&str] = &["pre", "script", "style", "textarea"];
+
+pub fn to_polite_html(parser: &dyn Tokenizer, orig: &str, policy: &MarkdownPolicy) -> String {
+    convert_html(parser, orig, Target::Polite, policy)
+}
+
+pub fn to_impolite_html(parser: &dyn Tokenizer, orig: &str, policy: &MarkdownPolicy) -> String {
+    convert_html(parser, orig, Target::Impolite, policy)
+}
+
+pub fn convert_html(
+    parser: &dyn Tokenizer,
+    orig: &str,
+    target: Target,
+    policy: &MarkdownPolicy,
+) -> String {
+    let mut result = String::with_capacity(orig.len());
+    let mut block = Vec::new();
+    // 開いている見出しや箇条書きの項目などの要素。段落の中身ならば空。
+    let mut kinds: Vec<BlockKind> = Vec::new();
+    // 変換しない要素の中にいる間は (要素名, 入れ子の深さ)
+    let mut skip: Option<(String, usize)> = None;
+    let mut code_level = 0usize;
+    let mut ruby_text_level = 0usize;
+
+    for token in tokenize(orig) {
+        if let Some((name, level)) = &mut skip {
+            if let Token::Tag(tag) = &token {
+                if tag.name == *name && tag.closing {
+                    *level -= 1;
+                } else if tag.name == *name && !tag.self_closing {
+                    *level += 1;
+                }
+            }
+            let finished = *level == 0;
+            result.push_str(token.raw());
+            if finished {
+                skip = None;
+            }
+            continue;
+        }
+
+        match token {
+            Token::Text(text) if ruby_text_level > 0 => block.push(Item::Markup(text)),
+            Token::Text(text) if code_level > 0 => block.push(Item::Code(text)),
+            Token::Text(text) => block.push(Item::Text(text)),
+            Token::Other(raw) => block.push(Item::Markup(raw)),
+            Token::Tag(tag) => {
+                let name = tag.name.as_str();
+                if INLINE.contains(&name) {
+                    let level = match name {
+                        "code" => Some(&mut code_level),
+                        "rt" | "rp" => Some(&mut ruby_text_level),
+                        _ => None,
+                    };
+                    if let Some(level) = level {
+                        if tag.closing {
+                            *level = level.saturating_sub(1);
+                        } else if !tag.self_closing {
+                            *level += 1;
+                        }
+                    }
+                    block.push(Item::Markup(tag.raw));
+                } else {
+                    let kind = kinds.last().copied().unwrap_or(BlockKind::Paragraph);
+                    flush(parser, target, kind, policy, &mut block, &mut result);
+                    if let Some(kind) = block_kind(name) {
+                        if tag.closing {
+                            if let Some(idx) = kinds.iter().rposition(|&k| k == kind) {
+                                kinds.truncate(idx);
+                            }
+                        } else if !tag.self_closing {
+                            kinds.push(kind);
+                        }
+                    }
+                    result.push_str(tag.raw);
+                    if SKIP.contains(&name) && !tag.closing && !tag.self_closing {
+                        skip = Some((tag.name, 1));
+                    }
+                }
+            }
+        }
+    }
+
+    let kind = kinds.last().copied().unwrap_or(BlockKind::Paragraph);
+    flush(parser, target, kind, policy, &mut block, &mut result);
+
+    result
+}
+
+/// 方針を段落と分ける要素のブロックの種類
+fn block_kind(name: &str) -> Option<BlockKind> {
+    match name {
+        "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => Some(BlockKind::Heading),
+        "li" | "dt" | "dd" => Some(BlockKind::ListItem),
+        "td" | "th" => Some(BlockKind::TableCell),
+        _ => None,
+    }
+}
+
+/// ブロック内の要素
+enum Item<'a> {
+    /// 変換するテキスト
+    Text(&'a str),
+    /// 文の一部として解析するが書き換えないテキスト
+    Code(&'a str),
+    /// そのまま出力するもの
+    Markup(&'a str),
+}
+
+/// ブロックの終わりで、たまったテキストをつなげて方針に従って変換して出力する。
+fn flush(
+    parser: &dyn Tokenizer,
+    target: Target,
+    kind: BlockKind,
+    policy: &MarkdownPolicy,
+    block: &mut Vec<Item>,
+    result: &mut String,
+) {
+    let runs: Vec<Run> = block
+        .iter()
+        .filter_map(|item| match *item {
+            Item::Text(text) => Some(Run {
+                text,
+                protected: false,
+            }),
+            Item::Code(text) => Some(Run {
+                text,
+                protected: true,
+            }),
+            Item::Markup(_) => None,
+        })
+        .collect();
+
+    // ソース上の改行やタブは単なる空白なので、文の区切りとみなさないよう空白にしてから解析する。
+    // いずれも 1 バイトなので位置はずれない。
+    let joined: String = runs
+        .iter()
+        .flat_map(|run| run.text.chars())
+        .map(|c| if c.is_ascii_whitespace() { ' ' } else { c })
+        .collect();
+
+    let edits = markdown::block_edits(parser, &joined, kind, target, policy);
+    let mut converted = edit::apply_to_runs(&runs, &edits).into_iter();
+    for item in block.drain(..) {
+        match item {
+            Item::Markup(raw) => result.push_str(raw),
+            Item::Text(_) | Item::Code(_) => {
+                if let Some(text) = converted.next() {
+                    result.push_str(&text);
+                }
+            }
+        }
+    }
+}
+
+struct Tag<'a> {
+    raw: &'a str,
+    /// 小文字にした要素名
+    name: String,
+    closing: bool,
+    self_closing: bool,
+}
+
+enum Token<'a> {
+    Text(&'a str),
+    Tag(Tag<'a>),
+    /// コメントや DOCTYPE、スクリプトの中身など
+    Other(&'a str),
+}
+
+impl<'a> Token<'a> {
+    fn raw(&self) -> &'a str {
+        match self {
+            Token::Text(raw) | Token::Other(raw) => *raw,
+            Token::Tag(tag) => tag.raw,
+        }
+    }
+}
+
+fn tokenize(orig: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut pos = 0;
+
+    while pos < orig.len() {
+        let rest = &orig[pos..];
+        let len = if rest.starts_with("<!--") {
+            let len = rest.find("-->").map_or(rest.len(), |idx| idx + "-->".len());
+            tokens.push(Token::Other(&rest[..len]));
+            len
+        } else if rest.starts_with("<!") || rest.starts_with("<?") {
+            let len = rest.find('>').map_or(rest.len(), |idx| idx + 1);
+            tokens.push(Token::Other(&rest[..len]));
+            len
+        } else if let Some(tag) = parse_tag(rest) {
+            let len = tag.raw.len();
+            let is_raw_text =
+                !tag.closing && !tag.self_closing && (tag.name == "script" || tag.name == "style");
+            let close = format!("</{}", tag.name);
+            tokens.push(Token::Tag(tag));
+
+            // スクリプトやスタイルの中身はタグとして解釈しない
+            if is_raw_text {
+                let body = &rest[len..];
+                let body_len = body.to_ascii_lowercase().find(&close).unwrap_or(body.len());
+                if body_len > 0 {
+                    tokens.push(Token::Other(&body[..body_len]));
+                }
+                len + body_len
+            } else {
+                len
+            }
+        } else {
+            // 次の '<' までがテキスト。先頭の '<' がタグでなかった場合はそれもテキストに含める。
+            let first = rest.chars().next().map_or(0, char::len_utf8);
+            let len = rest[first..]
+                .find('<')
+                .map_or(rest.len(), |idx| first + idx);
+            tokens.push(Token::Text(&rest[..len]));
+            len
+        };
+
+        pos += len;
+    }
+
+    tokens
+}
+
+fn parse_tag(rest: &str) -> Option<Tag> {
+    if !rest.starts_with('<') {
+        return None;
+    }
+
+    let closing = rest[1..].starts_with('/');
+    let name_start = if closing { 2 } else { 1 };
+    if !rest[name_start..].starts_with(|c: char| c.is_ascii_alphabetic()) {
+        return None;
+    }
+    let name_len = rest[name_start..]
+        .find(|c: char| !c.is_ascii_alphanumeric())
+        .unwrap_or(rest.len() - name_start);
+
+    // 属性値の中の '>' で終わらないよう引用符を追う
+    let mut quote = None;
+    let mut end = None;
+    for (idx, ch) in rest.char_indices().skip(name_start + name_len) {
+        match quote {
+            Some(q) if ch == q => quote = None,
+            Some(_) => {}
+            None => match ch {
+                '"' | '\'' => quote = Some(ch),
+                '>' => {
+                    end = Some(idx + 1);
+                    break;
+                }
+                _ => {}
+            },
+        }
+    }
+
+    let raw = &rest[..end?];
+    Some(Tag {
+        raw,
+        name: rest[name_start..name_start + name_len].to_ascii_lowercase(),
+        closing,
+        self_closing: raw.ends_with("/>"),
+    })
+}
+
+#[cfg(all(test, feature = "igo"))]
+mod tests {
+    use super::*;
+    use crate::test_parser;
+
+    #[test]
+    fn inline_elements() {
+        assert_eq!(
+            to_polite_html(
+                test_parser(),
+                "<p>今日は<b>晴天</b>だ。</p>\n<p>明日は<a href=\"/x\" title=\"a>b\">雨</a>だ。</p>",
+                &MarkdownPolicy::default()
+            ),
+            "<p>今日は<b>晴天</b>です。</p>\n<p>明日は<a href=\"/x\" title=\"a>b\">雨</a>です。</p>"
+        );
+    }
+
+    #[test]
+    fn skipped_elements() {
+        let orig = "<pre>今日は晴天だ。</pre><script>if (a < b) {}</script><p>今日は<ruby>晴天<rt>せいてん</rt></ruby>だ。</p>";
+        let expected = "<pre>今日は晴天だ。</pre><script>if (a < b) {}</script><p>今日は<ruby>晴天<rt>せいてん</rt></ruby>です。</p>";
+        assert_eq!(
+            to_polite_html(test_parser(), orig, &MarkdownPolicy::default()),
+            expected
+        );
+    }
+
+    #[test]
+    fn code_elements() {
+        assert_eq!(
+            to_polite_html(
+                test_parser(),
+                "<p><code>x</code>だ。</p>",
+                &MarkdownPolicy::default()
+            ),
+            "<p><code>x</code>です。</p>"
+        );
+        assert_eq!(
+            to_polite_html(
+                test_parser(),
+                "<p>値は<code>x</code>だ。</p>",
+                &MarkdownPolicy::default()
+            ),
+            "<p>値は<code>x</code>です。</p>"
+        );
+        assert_eq!(
+            to_polite_html(
+                test_parser(),
+                "<p>値は<code>x</code></p>",
+                &MarkdownPolicy::default()
+            ),
+            "<p>値は<code>x</code></p>"
+        );
+    }
+
+    #[test]
+    fn block_policy() {
+        let orig = "<h1>インストール方法</h1><ul><li>今日は晴天だ</li><li>設定方法</li></ul><table><tr><td>今日は晴天だ。</td></tr></table><p>今日は晴天だ</p>";
+        let expected = "<h1>インストール方法</h1><ul><li>今日は晴天です</li><li>設定方法</li></ul><table><tr><td>今日は晴天だ。</td></tr></table><p>今日は晴天です。</p>";
+        assert_eq!(
+            to_polite_html(test_parser(), orig, &MarkdownPolicy::default()),
+            expected
+        );
+    }
+}
@@ -0,0 +1,87 @@
+//! IPADIC 形式の素性の解釈。
+//!
+//! lindera や vibrato など、IPADIC の辞書を使う解析器で共通に使う。素性は「品詞, 品詞細分類1, 品詞細分類2, 品詞細分類3,
+//! 活用型, 活用形, 原形, 読み, 発音」の順に並んでいる。未知語では後ろが欠けていることがある。
+
+use crate::tokenizer::{
+    Conjugation, ConjugationForm, ConjugationKind, Morpheme, Postpositional, Symbol, WordClass,
+};
+
+/// 素性から形態素を作る。
+pub(crate) fn morpheme(surface: &str, start: usize, features: &[&str]) -> Morpheme {
+    let field = |idx: usize| features.get(idx).copied().filter(|&f| f != "*");
+
+    Morpheme {
+        surface: surface.to_string(),
+        basic: field(6).unwrap_or(surface).to_string(),
+        reading: field(7).unwrap_or("").to_string(),
+        pronunciation: field(8).unwrap_or("").to_string(),
+        wordclass: wordclass(field(0).unwrap_or(""), field(1).unwrap_or("")),
+        conjugation: conjugation(field(4).unwrap_or(""), field(5).unwrap_or("")),
+        start,
+    }
+}
+
+fn wordclass(pos: &str, sub: &str) -> WordClass {
+    match (pos, sub) {
+        ("名詞", _) => WordClass::Noun,
+        ("動詞", _) => WordClass::Verb,
+        ("形容詞", _) => WordClass::Adjective,
+        ("助動詞", _) => WordClass::AuxiliaryVerb,
+        ("助詞", "終助詞") => WordClass::Postpositional(Postpositional::End),
+        ("助詞", "副助詞／並立助詞／終助詞") => {
+            WordClass::Postpositional(Postpositional::SupplementaryParallelEnd)
+        }
+        ("助詞", "接続助詞") => WordClass::Postpositional(Postpositional::Conjunction),
+        ("助詞", _) => WordClass::Postpositional(Postpositional::Other),
+        ("記号", "句点") => WordClass::Symbol(Symbol::Period),
+        ("記号", "括弧開") => WordClass::Symbol(Symbol::OpenParen),
+        ("記号", "括弧閉") => WordClass::Symbol(Symbol::CloseParen),
+        ("記号", _) => WordClass::Symbol(Symbol::Other),
+        _ => WordClass::Other,
+    }
+}
+
+/// 活用型と活用形。typed-igo が igo の素性を読むときと同じ変換を使うので、igo で解析した場合と同じ値になる。
+/// 解釈できないものは活用しないものとして扱う。
+fn conjugation(kind: &str, form: &str) -> Conjugation {
+    Conjugation {
+        kind: kind.parse().unwrap_or(ConjugationKind::None),
+        form: form.parse().unwrap_or(ConjugationForm::None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn features() {
+        let m = morpheme(
+            "だ",
+            9,
+            &[
+                "助動詞",
+                "*",
+                "*",
+                "*",
+                "特殊・ダ",
+                "基本形",
+                "だ",
+                "ダ",
+                "ダ",
+            ],
+        );
+        assert_eq!(m.basic, "だ");
+        assert_eq!(m.reading, "ダ");
+        assert_eq!(m.wordclass, WordClass::AuxiliaryVerb);
+        assert!(matches!(m.conjugation.kind, ConjugationKind::SpecialDa));
+        assert!(matches!(m.conjugation.form, ConjugationForm::Basic));
+
+        // 未知語
+        let m = morpheme("ホゲ", 0, &["UNK"]);
+        assert_eq!(m.basic, "ホゲ");
+        assert_eq!(m.wordclass, WordClass::Other);
+        assert!(matches!(m.conjugation.kind, ConjugationKind::None));
+    }
+}
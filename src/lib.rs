@@ -1,53 +1,379 @@
+use rule::{verb_rule, Confidence, Rule};
 use scopefunc::ScopeFunc;
 use std::iter::FusedIterator;
-use typed_igo::conjugation::ConjugationForm;
-use typed_igo::{Conjugation, Morpheme, Parser};
+use std::ops::Range;
+use tokenizer::{Conjugation, ConjugationForm, Morpheme, MorphemeRef, Tokenizer};
+
+#[cfg(feature = "rayon")]
+pub mod batch;
+pub mod business;
+#[cfg(feature = "capi")]
+pub mod capi;
+pub mod casual;
+pub mod dialect;
+pub mod dictionary;
+pub mod edit;
+pub mod html;
+#[cfg(any(feature = "lindera", feature = "vibrato"))]
+mod ipadic;
+#[cfg(feature = "lindera")]
+pub mod lindera;
+pub mod lint;
+#[cfg(feature = "lsp")]
+pub mod lsp;
+pub mod markdown;
+#[cfg(feature = "python")]
+mod python;
+pub mod reading;
+pub mod result;
+pub mod rule;
+#[cfg(feature = "server")]
+pub mod server;
+pub mod stream;
+pub mod style;
+#[cfg(feature = "textlint")]
+pub mod textlint;
+pub mod tokenizer;
+pub mod verify;
+#[cfg(feature = "vibrato")]
+pub mod vibrato;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+/// 変換の向き。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum Target {
+    /// ですます調へ変換する。
+    Polite,
+    /// である調へ変換する。
+    Impolite,
+    /// くだけた話し言葉 (タメ口) へ変換する。話し方は `Options::persona` で指定する (`convert()` などでは
+    /// `casual::Persona` の既定のもの)。
+    Casual,
+    /// 「でございます」「おります」などの改まった形へ変換する。
+    Formal,
+}
 
-pub fn to_polite_sentence(parser: &Parser, orig: &str) -> String {
-    use typed_igo::conjugation::ConjugationForm as F;
+/// 丁寧さの度合い。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PolitenessLevel {
+    /// である調
+    Plain,
+    /// ですます調
+    Teinei,
+    /// 「でございます」「おります」などの改まった形
+    Formal,
+}
 
-    parser
-        .parse(orig)
-        .transform(Splitter::new)
-        .break_into_parts()
+pub fn convert(parser: &dyn Tokenizer, orig: &str, target: Target) -> String {
+    match target {
+        Target::Polite => to_polite_sentence(parser, orig),
+        Target::Impolite => to_impolite_sentence(parser, orig),
+        Target::Casual => casual::to_casual_sentence(parser, orig, &casual::Persona::default()),
+        Target::Formal => to_formal_sentence(parser, orig),
+    }
+}
+
+pub fn to_polite_sentence(parser: &dyn Tokenizer, orig: &str) -> String {
+    use crate::tokenizer::ConjugationForm as F;
+
+    parts(&parser.tokenize(orig))
         .into_iter()
-        .map(|part| part.into_polite(F::Basic))
+        .map(|part| part.into_polite(F::Basic).into_best())
         .collect()
 }
 
-pub fn to_impolite_sentence(parser: &Parser, orig: &str) -> String {
-    use typed_igo::conjugation::ConjugationForm as F;
+pub fn to_impolite_sentence(parser: &dyn Tokenizer, orig: &str) -> String {
+    use crate::tokenizer::ConjugationForm as F;
 
-    parser
-        .parse(orig)
-        .transform(Splitter::new)
-        .break_into_parts()
+    parts(&parser.tokenize(orig))
+        .into_iter()
+        .map(|part| part.into_impolite(&[F::Basic]).into_best())
+        .collect()
+}
+
+pub fn to_formal_sentence(parser: &dyn Tokenizer, orig: &str) -> String {
+    parts(&parser.tokenize(orig))
+        .into_iter()
+        .map(|part| part.into_formal().into_best())
+        .collect()
+}
+
+/// `level` の丁寧さに変換する。
+pub fn to_politeness_level(parser: &dyn Tokenizer, orig: &str, level: PolitenessLevel) -> String {
+    match level {
+        PolitenessLevel::Plain => to_impolite_sentence(parser, orig),
+        PolitenessLevel::Teinei => to_polite_sentence(parser, orig),
+        PolitenessLevel::Formal => to_formal_sentence(parser, orig),
+    }
+}
+
+/// 変換の設定。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Options {
+    pub target: Target,
+    /// これより確信度の低い規則が必要な文は変換せず、要確認とする。`None` なら常に変換する。
+    pub min_confidence: Option<Confidence>,
+    /// `Target::Casual` のときの話し方
+    pub persona: casual::Persona,
+}
+
+impl Options {
+    pub fn new(target: Target) -> Options {
+        Options {
+            target,
+            min_confidence: None,
+            persona: casual::Persona::default(),
+        }
+    }
+
+    /// 確信度の高い規則だけを使う。
+    pub fn conservative(target: Target) -> Options {
+        Options {
+            min_confidence: Some(Confidence::High),
+            ..Options::new(target)
+        }
+    }
+
+    /// `Target::Casual` のときの話し方を指定する。
+    pub fn persona(mut self, persona: casual::Persona) -> Options {
+        self.persona = persona;
+        self
+    }
+}
+
+/// 文ごとの変換結果。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SentenceResult {
+    /// 元の文字列での位置
+    pub range: Range<usize>,
+    pub original: String,
+    /// 変換後の文。変換を見送った場合は元の文のまま。
+    pub converted: String,
+    /// 区間ごとに適用した (見送った場合は適用するはずだった) 規則
+    pub rules: Vec<Rule>,
+    /// 適用した規則のうち最も低い確信度
+    pub confidence: Confidence,
+    /// 確信度が足りずに変換を見送ったので、人が確認する必要がある
+    pub needs_review: bool,
+}
+
+/// 文ごとに変換し、適用した規則や確信度とともに返す。
+pub fn convert_sentences(
+    parser: &dyn Tokenizer,
+    orig: &str,
+    options: &Options,
+) -> Vec<SentenceResult> {
+    edit::sentence_ranges(orig)
         .into_iter()
-        .map(|part| part.into_impolite(&[F::Basic]))
+        .map(|range| {
+            let original = &orig[range.clone()];
+            let (converted, rules) = convert_sentence(parser, original, options);
+            let confidence = rules
+                .iter()
+                .map(|rule| rule.confidence())
+                .min()
+                .unwrap_or(Confidence::High);
+            let needs_review = options.min_confidence.is_some_and(|min| confidence < min);
+
+            SentenceResult {
+                range,
+                original: original.to_string(),
+                converted: if needs_review {
+                    original.to_string()
+                } else {
+                    converted
+                },
+                rules,
+                confidence,
+                needs_review,
+            }
+        })
         .collect()
 }
 
-struct Part<'t, 'd> {
-    morphs: Vec<Morpheme<'t, 'd>>,
-    sep: Option<Morpheme<'t, 'd>>,
+/// `options` に従って変換する。確信度が足りない文はそのまま残す。
+pub fn convert_with(parser: &dyn Tokenizer, orig: &str, options: &Options) -> String {
+    edit::apply(orig, &edit::edits_with(parser, orig, options))
+}
+
+fn convert_sentence(
+    parser: &dyn Tokenizer,
+    sentence: &str,
+    options: &Options,
+) -> (String, Vec<Rule>) {
+    use crate::tokenizer::ConjugationForm as F;
+
+    let target = options.target;
+    let mut rules = Vec::new();
+    let converted: String = parts(&parser.tokenize(sentence))
+        .into_iter()
+        .map(|part| {
+            let conversion = match target {
+                Target::Polite => part.into_polite(F::Basic),
+                Target::Impolite | Target::Casual => part.into_impolite(&[F::Basic]),
+                Target::Formal => part.into_formal(),
+            };
+            rules.push(conversion.rule);
+            conversion.into_best()
+        })
+        .collect();
+
+    match target {
+        Target::Casual => (
+            casual::casualize(parser, &converted, &options.persona),
+            rules,
+        ),
+        _ => (converted, rules),
+    }
+}
+
+/// 文ごとの変換候補。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Candidates {
+    /// 元の文字列での位置
+    pub range: Range<usize>,
+    /// よさそうな順に並んでいる。先頭は `convert()` の結果と同じ。
+    pub candidates: Vec<String>,
+}
+
+/// 文ごとに、変換結果の候補を複数返す。
+///
+/// 最初に当てはまった規則だけでなく、ほかに当てはまる規則でも変換した結果を候補にする。
+/// (例: 「寒くなかった」 -> 「寒くありませんでした」「寒くなかったです」)
+pub fn candidates(parser: &dyn Tokenizer, orig: &str, target: Target) -> Vec<Candidates> {
+    candidates_with(parser, orig, &Options::new(target))
+}
+
+/// `options` に従って、文ごとに変換結果の候補を複数返す。確信度は見ない。
+pub fn candidates_with(parser: &dyn Tokenizer, orig: &str, options: &Options) -> Vec<Candidates> {
+    edit::sentence_ranges(orig)
+        .into_iter()
+        .map(|range| {
+            let candidates = sentence_candidates(parser, &orig[range.clone()], options);
+            Candidates { range, candidates }
+        })
+        .collect()
+}
+
+fn sentence_candidates(parser: &dyn Tokenizer, sentence: &str, options: &Options) -> Vec<String> {
+    use crate::tokenizer::ConjugationForm as F;
+
+    let target = options.target;
+
+    // 話し言葉の候補はである調の候補を崩したもの
+    if target == Target::Casual {
+        let impolite = Options::new(Target::Impolite);
+        let mut candidates = Vec::new();
+        for candidate in sentence_candidates(parser, sentence, &impolite) {
+            let candidate = casual::casualize(parser, &candidate, &options.persona);
+            if !candidates.contains(&candidate) {
+                candidates.push(candidate);
+            }
+        }
+        return candidates;
+    }
+
+    let parts: Vec<Vec<String>> = parts(&parser.tokenize(sentence))
+        .into_iter()
+        .map(|part| match target {
+            Target::Polite => part.into_polite(F::Basic).candidates,
+            Target::Impolite | Target::Casual => part.into_impolite(&[F::Basic]).candidates,
+            Target::Formal => part.into_formal().candidates,
+        })
+        .collect();
+
+    // 一番よい候補を並べたものを先頭に、そこから一つの区間だけを別の候補に差し替えたものを続ける。
+    let best: Vec<&str> = parts.iter().map(|part| part[0].as_str()).collect();
+    let mut candidates = vec![best.concat()];
+    for (idx, part) in parts.iter().enumerate() {
+        for alternative in &part[1..] {
+            let mut pieces = best.clone();
+            pieces[idx] = alternative;
+            let candidate = pieces.concat();
+            if !candidates.contains(&candidate) {
+                candidates.push(candidate);
+            }
+        }
+    }
+
+    candidates
 }
 
-impl<'t, 'd> Part<'t, 'd> {
-    fn new(morphs: Vec<Morpheme<'t, 'd>>) -> Part<'t, 'd> {
+/// 形態素列を変換の単位に分ける。
+fn parts(morphs: &[Morpheme]) -> Vec<Part> {
+    morphs
+        .iter()
+        .map(Morpheme::view)
+        .transform(Splitter::new)
+        .break_into_parts()
+}
+
+#[derive(Clone)]
+struct Part<'a> {
+    morphs: Vec<MorphemeRef<'a>>,
+    sep: Option<MorphemeRef<'a>>,
+}
+
+/// 区間を変換した結果。
+struct Conversion {
+    /// 適用した規則
+    rule: Rule,
+    /// 変換結果の候補。よさそうな順に並んでいて、先頭が変換結果。区切りも含む。
+    candidates: Vec<String>,
+}
+
+impl Conversion {
+    /// `bodies` のそれぞれに `suffix` (終助詞と区切り) を付けたものを候補にする。
+    fn new(rule: Rule, bodies: Vec<String>, suffix: &str) -> Conversion {
+        let mut candidates: Vec<String> = Vec::with_capacity(bodies.len());
+        for body in bodies {
+            let candidate = body + suffix;
+            if !candidates.contains(&candidate) {
+                candidates.push(candidate);
+            }
+        }
+
+        Conversion { rule, candidates }
+    }
+
+    /// 一番よい候補。
+    fn into_best(self) -> String {
+        self.candidates.into_iter().next().unwrap_or_default()
+    }
+}
+
+impl<'a> Part<'a> {
+    fn new(morphs: Vec<MorphemeRef<'a>>) -> Part<'a> {
         Part { morphs, sep: None }
     }
 
-    fn with_sep(morphs: Vec<Morpheme<'t, 'd>>, sep: Morpheme<'t, 'd>) -> Part<'t, 'd> {
+    fn with_sep(morphs: Vec<MorphemeRef<'a>>, sep: MorphemeRef<'a>) -> Part<'a> {
         Part {
             morphs,
             sep: Some(sep),
         }
     }
 
-    fn into_polite(self, last_form: ConjugationForm) -> String {
-        use typed_igo::conjugation::ConjugationForm as F;
-        use typed_igo::Morpheme as M;
-        use typed_igo::WordClass as W;
+    fn sep_surface(&self) -> &str {
+        self.sep.as_ref().map(|x| x.surface).unwrap_or("")
+    }
+
+    /// 元の文字列にある区切り。`create_period()` で補った句点は含まない。
+    fn orig_sep(&self) -> Option<&MorphemeRef<'a>> {
+        self.sep.as_ref().filter(|sep| sep.start != !0)
+    }
+
+    /// ですます調に変換する。
+    fn into_polite(self, last_form: ConjugationForm) -> Conversion {
+        use crate::tokenizer::ConjugationForm as F;
+        use crate::tokenizer::MorphemeRef as M;
+        use crate::tokenizer::WordClass as W;
 
         let Part { mut morphs, sep } = self;
         let sep_surface = sep.map(|x| x.surface).unwrap_or("");
@@ -58,25 +384,32 @@ impl<'t, 'd> Part<'t, 'd> {
         // 次に最後の単語を取り出す。もし単語がなければ即 String へ
         let last = match morphs.pop() {
             Some(last) => last,
-            None => return ends + sep_surface,
+            None => return Conversion::new(Rule::Empty, vec![ends + sep_surface], ""),
         };
 
+        // 終助詞を除いた元の形。ほかに当てはまる規則で変換した候補を作るときに使う。
+        let whole = morphs_to_string(&morphs) + last.surface;
+
         // 文末を処理するもの
-        let fixlast = |orig: &str| match (orig, last_form) {
-            ("です", F::Basic) => "です",
-            ("です", F::NegativeU) => "でしょ",
+        // 対応していない組み合わせは活用させずにそのまま返す。
+        let fixlast = |orig: &'static str| -> &'static str {
+            match (orig, last_form) {
+                ("です", F::Basic) => "です",
+                ("です", F::NegativeU) => "でしょ",
 
-            ("ます", F::Basic) => "ます",
-            ("ます", F::Negative) => "ませ",
-            ("ます", F::NegativeU) => "ましょ",
+                ("ます", F::Basic) => "ます",
+                ("ます", F::Negative) => "ませ",
+                ("ます", F::NegativeU) => "ましょ",
 
-            other => panic!("unsupported pair: {:?}", other),
+                (orig, _) => orig,
+            }
         };
 
         // とりあえず基本的には最後の単語を変換していけばよいが、いくつか例外もある。
         //
         // - 「です」「ます」 : 変換の必要なし
         // - 助動詞の「だ」 : 「です」へ変換
+        // - 「ください」 : 変換の必要なし
         // - 動詞 : 連用形に変換して「ます」を追加
         // - 「ある」
         //   - 「である」 : 合わせて「です」へ変換
@@ -95,7 +428,14 @@ impl<'t, 'd> Part<'t, 'd> {
         // - 「しよう」などの 「う」 : 未然ウ接続終わりの into_polite() して「う」を追加
         // - 否定の「ん」未然終わりの into_polite() して「ん」を追加
         // - それ以外 : 「です」を追加
-        let without_sep = match last {
+        //
+        // ほかにも当てはまる規則があれば、その変換結果も候補として後ろに続ける。
+        //
+        // - 「である」 : 「であります」に変換
+        // - 「ない」 (「でない」を除く) : 「です」を追加 (寒くない -> 寒くないです)
+        // - 「ない」+ 過去の「た」 : 「です」を追加 (寒くなかった -> 寒くなかったです)
+        // - 「しよう」などの「う」 : 「と思います」を追加
+        let (rule, without_sep) = match last {
             // 「です」「ます」
             M {
                 basic: "です",
@@ -106,27 +446,47 @@ impl<'t, 'd> Part<'t, 'd> {
                 basic: "ます",
                 surface,
                 ..
-            } => morphs_to_string(&morphs) + surface,
+            } => (
+                Rule::AlreadyPolite,
+                vec![morphs_to_string(&morphs) + surface],
+            ),
 
             // 助動詞の「だ」
             M {
                 wordclass: W::AuxiliaryVerb,
                 basic: "だ",
                 ..
-            } => morphs_to_string(&morphs) + fixlast("です"),
+            } => (
+                Rule::Copula,
+                vec![morphs_to_string(&morphs) + fixlast("です")],
+            ),
+
+            // 「ください」
+            M {
+                wordclass: W::Verb,
+                basic: "くださる",
+                surface,
+                ..
+            } => (
+                Rule::AlreadyPolite,
+                vec![morphs_to_string(&morphs) + surface],
+            ),
 
             // 動詞
             M {
-                wordclass: W::Verb(_),
+                wordclass: W::Verb,
                 basic,
                 surface,
                 conjugation,
                 ..
-            } => {
-                morphs_to_string(&morphs)
-                    + &make_continuous(basic, surface, conjugation)
-                    + fixlast("ます")
-            }
+            } => (
+                verb_rule(conjugation.kind, Rule::Verb),
+                vec![
+                    morphs_to_string(&morphs)
+                        + &make_continuous(basic, surface, conjugation)
+                        + fixlast("ます"),
+                ],
+            ),
 
             // 「ある」
             M {
@@ -138,11 +498,18 @@ impl<'t, 'd> Part<'t, 'd> {
                     wordclass: W::AuxiliaryVerb,
                     basic: "だ",
                     ..
-                }) => morphs_to_string(&morphs) + fixlast("です"),
-                Some(M { surface, .. }) => {
-                    morphs_to_string(&morphs) + surface + "あり" + fixlast("ます")
-                }
-                None => "あり".to_string() + fixlast("ます"),
+                }) => (
+                    Rule::Dearu,
+                    vec![
+                        morphs_to_string(&morphs) + fixlast("です"),
+                        morphs_to_string(&morphs) + "であります",
+                    ],
+                ),
+                Some(M { surface, .. }) => (
+                    Rule::Aru,
+                    vec![morphs_to_string(&morphs) + surface + "あり" + fixlast("ます")],
+                ),
+                None => (Rule::Aru, vec!["あり".to_string() + fixlast("ます")]),
             },
 
             // 「ない」
@@ -152,7 +519,7 @@ impl<'t, 'd> Part<'t, 'd> {
                 ..
             }
             | M {
-                wordclass: W::Adjective(_),
+                wordclass: W::Adjective,
                 basic: "ない",
                 ..
             } => match morphs.pop() {
@@ -160,25 +527,44 @@ impl<'t, 'd> Part<'t, 'd> {
                     wordclass: W::AuxiliaryVerb,
                     basic: "で",
                     ..
-                }) => morphs_to_string(&morphs) + "ではありません",
+                }) => (
+                    Rule::DeNai,
+                    vec![morphs_to_string(&morphs) + "ではありません"],
+                ),
                 Some(M {
-                    wordclass: W::Verb(_),
+                    wordclass: W::Verb,
                     basic,
                     surface,
                     conjugation,
                     ..
-                }) => {
-                    morphs_to_string(&morphs)
-                        + &make_continuous(basic, surface, conjugation)
-                        + "ません"
-                }
+                }) => (
+                    verb_rule(conjugation.kind, Rule::VerbNai),
+                    vec![
+                        morphs_to_string(&morphs)
+                            + &make_continuous(basic, surface, conjugation)
+                            + "ません",
+                        whole + "です",
+                    ],
+                ),
                 Some(M {
-                    wordclass: W::Adjective(_),
+                    wordclass: W::Adjective,
                     surface,
                     ..
-                }) => morphs_to_string(&morphs) + surface + "ありません",
-                Some(M { surface, .. }) => morphs_to_string(&morphs) + surface + "ありません",
-                None => "ありません".into(),
+                }) => (
+                    Rule::AdjectiveNai,
+                    vec![
+                        morphs_to_string(&morphs) + surface + "ありません",
+                        whole + "です",
+                    ],
+                ),
+                Some(M { surface, .. }) => (
+                    Rule::OtherNai,
+                    vec![
+                        morphs_to_string(&morphs) + surface + "ありません",
+                        whole + "です",
+                    ],
+                ),
+                None => (Rule::OtherNai, vec!["ありません".into(), whole + "です"]),
             },
 
             // 過去の「た」
@@ -196,61 +582,221 @@ impl<'t, 'd> Part<'t, 'd> {
                     basic: "ます",
                     surface,
                     ..
-                }) => morphs_to_string(&morphs) + surface + "た",
+                }) => (
+                    Rule::AlreadyPolite,
+                    vec![morphs_to_string(&morphs) + surface + "た"],
+                ),
                 Some(M {
-                    wordclass: W::Verb(_),
+                    wordclass: W::Verb,
                     basic,
                     surface,
                     conjugation,
                     ..
-                }) => {
-                    morphs_to_string(&morphs)
-                        + &make_continuous(basic, surface, conjugation)
-                        + "ました"
-                }
+                }) => (
+                    verb_rule(conjugation.kind, Rule::PastVerb),
+                    vec![
+                        morphs_to_string(&morphs)
+                            + &make_continuous(basic, surface, conjugation)
+                            + "ました",
+                    ],
+                ),
                 Some(M {
                     wordclass: W::AuxiliaryVerb,
                     basic: "だ",
                     ..
-                }) => morphs_to_string(&morphs) + "でした",
+                }) => (Rule::PastCopula, vec![morphs_to_string(&morphs) + "でした"]),
                 // である -> でした
                 Some(M {
                     wordclass: W::AuxiliaryVerb,
                     basic: "ある",
                     ..
-                }) => morphs_to_string(&morphs) + "した",
+                }) => (Rule::PastDearu, vec![morphs_to_string(&morphs) + "した"]),
                 Some(
                     morph @ M {
                         basic: "ない", ..
                     },
-                ) => {
-                    morphs
-                        .modify(|ms| ms.push(morph))
-                        .transform(Part::new)
-                        .into_polite(F::Basic)
-                        + "でした"
-                }
-                Some(M { surface, .. }) => morphs_to_string(&morphs) + surface + "たです",
-                None => "たです".to_string(),
+                ) => (
+                    Rule::PastNai,
+                    vec![
+                        morphs
+                            .modify(|ms| ms.push(morph))
+                            .transform(Part::new)
+                            .into_polite(F::Basic)
+                            .into_best()
+                            + "でした",
+                        whole + "です",
+                    ],
+                ),
+                Some(M { surface, .. }) => (
+                    Rule::PastFallback,
+                    vec![morphs_to_string(&morphs) + surface + "たです"],
+                ),
+                None => (Rule::PastFallback, vec!["たです".to_string()]),
             },
 
             // 「しよう」などの 「う」
-            M { basic: "う", .. } => Part::new(morphs).into_polite(F::NegativeU) + "う",
+            M { basic: "う", .. } => (
+                Rule::Volitional,
+                vec![
+                    Part::new(morphs).into_polite(F::NegativeU).into_best() + "う",
+                    whole + "と思います",
+                ],
+            ),
 
             // 否定の「ん」
-            M { basic: "ん", .. } => Part::new(morphs).into_polite(F::Negative) + "ん",
+            M { basic: "ん", .. } => (
+                Rule::NegativeNn,
+                vec![Part::new(morphs).into_polite(F::Negative).into_best() + "ん"],
+            ),
 
             // それ以外
-            M { surface, .. } => morphs_to_string(&morphs) + surface + "です",
+            M { surface, .. } => (
+                Rule::AppendDesu,
+                vec![morphs_to_string(&morphs) + surface + "です"],
+            ),
+        };
+
+        Conversion::new(rule, without_sep, &(ends + sep_surface))
+    }
+
+    /// 「でございます」などの改まった形に変換する。
+    ///
+    /// - 助動詞の「だ」、「である」、「です」 : 「でございます」に変換
+    ///   - 形容詞 + 「です」 : 形容詞をウ音便にして「ございます」を追加
+    /// - 形容詞の終止形 : ウ音便にして「ございます」を追加
+    /// - 「ある」「あります」 : 「ございます」に変換
+    /// - 「いる」「います」 : 「おります」に変換
+    /// - それ以外 : `into_polite()` と同じ
+    ///
+    /// 改まった形に変換したときは、ですます調の候補を後ろに続ける。
+    fn into_formal(self) -> Conversion {
+        use crate::tokenizer::ConjugationForm as F;
+        use crate::tokenizer::MorphemeRef as M;
+        use crate::tokenizer::WordClass as W;
+
+        let polite = self.clone().into_polite(F::Basic);
+        let (body, ends) = split_ends(&self.morphs);
+        let formal = match body {
+            [rest @ .., M {
+                wordclass: W::AuxiliaryVerb,
+                basic: "だ",
+                surface: "だ",
+                ..
+            }]
+            | [rest @ .., M {
+                wordclass: W::AuxiliaryVerb,
+                basic: "だ",
+                ..
+            }, M {
+                wordclass: W::AuxiliaryVerb,
+                basic: "ある",
+                surface: "ある",
+                ..
+            }] => Some((
+                Rule::FormalCopula,
+                vec![morphs_to_string(rest) + "でございます"],
+            )),
+
+            [rest @ .., adj @ M {
+                wordclass: W::Adjective,
+                ..
+            }, M {
+                wordclass: W::AuxiliaryVerb,
+                basic: "です",
+                surface: "です",
+                ..
+            }]
+            | [rest @ .., adj @ M {
+                wordclass: W::Adjective,
+                ..
+            }] if adj.basic != "ない" && matches!(adj.conjugation.form, F::Basic) => {
+                u_onbin(adj.surface).map(|adj| {
+                    (
+                        Rule::FormalAdjective,
+                        vec![morphs_to_string(rest) + &adj + "ございます"],
+                    )
+                })
+            }
+
+            // 「寒かったです」などはそのまま
+            [.., M {
+                wordclass: W::AuxiliaryVerb,
+                basic: "た",
+                ..
+            }, M {
+                wordclass: W::AuxiliaryVerb,
+                basic: "です",
+                ..
+            }] => None,
+            [rest @ .., M {
+                wordclass: W::AuxiliaryVerb,
+                basic: "です",
+                surface: "です",
+                ..
+            }] => Some((
+                Rule::FormalCopula,
+                vec![morphs_to_string(rest) + "でございます"],
+            )),
+
+            [rest @ .., M {
+                wordclass: W::Verb | W::AuxiliaryVerb,
+                basic: "ある",
+                surface: "ある",
+                ..
+            }]
+            | [rest @ .., M {
+                wordclass: W::Verb | W::AuxiliaryVerb,
+                basic: "ある",
+                surface: "あり",
+                ..
+            }, M {
+                wordclass: W::AuxiliaryVerb,
+                basic: "ます",
+                surface: "ます",
+                ..
+            }] => Some((Rule::FormalAru, vec![morphs_to_string(rest) + "ございます"])),
+
+            [rest @ .., M {
+                wordclass: W::Verb,
+                basic: "いる",
+                surface: "いる",
+                ..
+            }]
+            | [rest @ .., M {
+                wordclass: W::Verb,
+                basic: "いる",
+                surface: "い",
+                ..
+            }, M {
+                wordclass: W::AuxiliaryVerb,
+                basic: "ます",
+                surface: "ます",
+                ..
+            }] => Some((Rule::FormalIru, vec![morphs_to_string(rest) + "おります"])),
+
+            _ => None,
         };
 
-        without_sep + &ends + sep_surface
+        match formal {
+            Some((rule, bodies)) => {
+                let suffix = morphs_to_string(ends) + self.sep_surface();
+                let mut conversion = Conversion::new(rule, bodies, &suffix);
+                for candidate in polite.candidates {
+                    if !conversion.candidates.contains(&candidate) {
+                        conversion.candidates.push(candidate);
+                    }
+                }
+                conversion
+            }
+            None => polite,
+        }
     }
 
-    fn into_impolite(self, last_forms: &[ConjugationForm]) -> String {
-        use typed_igo::conjugation::{ConjugationForm as F, ConjugationKind as K};
-        use typed_igo::Morpheme as M;
-        use typed_igo::WordClass as W;
+    /// である調に変換する。
+    fn into_impolite(self, last_forms: &[ConjugationForm]) -> Conversion {
+        use crate::tokenizer::MorphemeRef as M;
+        use crate::tokenizer::WordClass as W;
+        use crate::tokenizer::{ConjugationForm as F, ConjugationKind as K};
 
         let Part { mut morphs, sep } = self;
         let sep_surface = sep.map(|x| x.surface).unwrap_or("");
@@ -261,15 +807,11 @@ impl<'t, 'd> Part<'t, 'd> {
         // 最後の単語を取り出す。単語がなければ即 String にして終わり。
         let last = match morphs.pop() {
             Some(last) => last,
-            None => return ends + sep_surface,
+            None => return Conversion::new(Rule::Empty, vec![ends + sep_surface], ""),
         };
 
         // 活用を処理するもの
-        let fix = |orig: &str, kind: K, from: F, to: &[F]| {
-            to.iter()
-                .find_map(|&to| conjugation::convert(orig, kind, from, to).ok())
-                .unwrap_or_else(|| orig.to_string())
-        };
+        let fix = conjugate;
 
         let fixlast = |orig: &str| match orig {
             "だ" => fix("だ", K::SpecialDa, F::Basic, last_forms),
@@ -278,7 +820,7 @@ impl<'t, 'd> Part<'t, 'd> {
             "です" => fix("です", K::SpecialDesu, F::Basic, last_forms),
             "ます" => fix("ます", K::SpecialMasu, F::Basic, last_forms),
             "ない" => fix("ない", K::SpecialNai, F::Basic, last_forms),
-            _ => panic!("unsupported conversion"),
+            other => other.to_string(),
         };
 
         // とりあえず基本的には最後の単語を変換していけばよいが、いくつか例外もある。
@@ -290,6 +832,7 @@ impl<'t, 'd> Part<'t, 'd> {
         //   - 過去「た」 : 単に消す
         //   - それ以外 : 終助詞がなければ「だ」に変換
         // - 「ます」 : 一つ前で場合分け
+        //   - 「ござる」「おる」 : 前とまとめて普通の形にする (でございます -> だ、おります -> いる)
         //   - 動詞 : 消して終止形にする
         //   - それ以外 : FIXME: 単に消す
         // - Let's の「う」 : 一つ前で場合分け
@@ -297,27 +840,39 @@ impl<'t, 'd> Part<'t, 'd> {
         //   - 「ます」 : 一つ前を連用ウ接続にして「う」に変換
         // - 否定の「ん」 : 一つ前で場合分け
         //   - 「ます」 : 一つ前で場合分け
-        //     - 「ある」 : まとめて「ない」に変換
+        //     - 「ある」「ござる」 : まとめて「ない」に変換
+        //     - 「おる」 : まとめて「いない」に変換
         //     - それ以外 : 一つ前を未然形に変換し、「ます」「ん」を「ない」に変換
         //   - それ以外 : FIXME: 「ない」に変換
         // - 過去の「た」 : 一つ前で場合分け
         //   - 「です」 : 一つ前までで再変換し連用タ接続、「た」を追加する。
-        //   - 「ます」 : 一つ前を連用タ接続、「た」を追加する。
+        //   - 「ます」 : 一つ前を連用タ接続、「た」を追加する。「ござる」「おる」は前とまとめて普通の形にする。
         // - それ以外 : 変換の必要なし
-        let without_sep = match last {
+        //
+        // ほかにも当てはまる規則があれば、その変換結果も候補として後ろに続ける。
+        //
+        // - 「です」 (形容詞と過去の「た」の後ろを除く) : 終助詞がなければ「である」に変換
+        // - 「でした」 : 「であった」に変換
+        let (rule, without_sep) = match last {
             // 助動詞の「だ」
             M {
                 wordclass: W::AuxiliaryVerb,
                 basic: "だ",
                 ..
-            } => morphs_to_string(&morphs) + &fixlast("だ"),
+            } => (
+                Rule::AlreadyPlain,
+                vec![morphs_to_string(&morphs) + &fixlast("だ")],
+            ),
 
             // 助動詞の「ある」
             M {
                 wordclass: W::AuxiliaryVerb,
                 basic: "ある",
                 ..
-            } => morphs_to_string(&morphs) + &fixlast("ある"),
+            } => (
+                Rule::AlreadyPlain,
+                vec![morphs_to_string(&morphs) + &fixlast("ある")],
+            ),
 
             // 「です」
             M {
@@ -326,29 +881,37 @@ impl<'t, 'd> Part<'t, 'd> {
                 ..
             } => match morphs.pop() {
                 Some(M {
-                    wordclass: W::Adjective(_),
+                    wordclass: W::Adjective,
                     surface,
                     ..
-                }) => morphs_to_string(&morphs) + surface,
+                }) => (
+                    Rule::DesuAfterAdjective,
+                    vec![morphs_to_string(&morphs) + surface],
+                ),
                 Some(M {
                     wordclass: W::AuxiliaryVerb,
                     basic: "た",
                     ..
-                }) => morphs_to_string(&morphs) + &fixlast("た"),
+                }) => (
+                    Rule::DesuAfterPast,
+                    vec![morphs_to_string(&morphs) + &fixlast("た")],
+                ),
                 Some(M { surface, .. }) => {
-                    morphs_to_string(&morphs)
-                        + surface
-                        + &if ends.is_empty() {
-                            fixlast("だ")
-                        } else {
-                            "".into()
-                        }
+                    let rest = morphs_to_string(&morphs) + surface;
+                    if ends.is_empty() {
+                        (
+                            Rule::Desu,
+                            vec![rest.clone() + &fixlast("だ"), rest + "である"],
+                        )
+                    } else {
+                        (Rule::Desu, vec![rest])
+                    }
                 }
                 None => {
                     if ends.is_empty() {
-                        fixlast("だ")
+                        (Rule::Desu, vec![fixlast("だ")])
                     } else {
-                        "".into()
+                        (Rule::Desu, vec!["".into()])
                     }
                 }
             },
@@ -360,13 +923,26 @@ impl<'t, 'd> Part<'t, 'd> {
                 ..
             } => match morphs.pop() {
                 Some(M {
-                    wordclass: W::Verb(_),
+                    wordclass: W::Verb,
+                    basic,
+                    ..
+                }) if basic == "ござる" || basic == "おる" => (
+                    Rule::Formal,
+                    vec![plain_of_formal(&mut morphs, basic, last_forms)],
+                ),
+                Some(M {
+                    wordclass: W::Verb,
                     surface,
                     conjugation: Conjugation { form, kind },
                     ..
-                }) => morphs_to_string(&morphs) + &fix(surface, kind, form, last_forms),
-                Some(M { surface, .. }) => morphs_to_string(&morphs) + surface,
-                None => "".into(),
+                }) => (
+                    Rule::MasuAfterVerb,
+                    vec![morphs_to_string(&morphs) + &fix(surface, kind, form, last_forms)],
+                ),
+                Some(M { surface, .. }) => {
+                    (Rule::MasuOther, vec![morphs_to_string(&morphs) + surface])
+                }
+                None => (Rule::MasuOther, vec!["".into()]),
             },
 
             // 「う」
@@ -379,7 +955,10 @@ impl<'t, 'd> Part<'t, 'd> {
                     wordclass: W::AuxiliaryVerb,
                     basic: "です",
                     ..
-                }) => morphs_to_string(&morphs) + "だろう",
+                }) => (
+                    Rule::VolitionalDesu,
+                    vec![morphs_to_string(&morphs) + "だろう"],
+                ),
                 Some(M {
                     wordclass: W::AuxiliaryVerb,
                     basic: "ます",
@@ -389,15 +968,21 @@ impl<'t, 'd> Part<'t, 'd> {
                         surface,
                         conjugation: Conjugation { kind, form },
                         ..
-                    }) => {
-                        morphs_to_string(&morphs)
-                            + &fix(surface, kind, form, &[F::NegativeU, F::Negative])
-                            + "う"
-                    }
-                    None => "う".into(),
+                    }) => (
+                        Rule::VolitionalMasu,
+                        vec![
+                            morphs_to_string(&morphs)
+                                + &fix(surface, kind, form, &[F::NegativeU, F::Negative])
+                                + "う",
+                        ],
+                    ),
+                    None => (Rule::VolitionalMasu, vec!["う".into()]),
                 },
-                Some(M { surface, .. }) => morphs_to_string(&morphs) + surface + "う",
-                None => "う".into(),
+                Some(M { surface, .. }) => (
+                    Rule::VolitionalOther,
+                    vec![morphs_to_string(&morphs) + surface + "う"],
+                ),
+                None => (Rule::VolitionalOther, vec!["う".into()]),
             },
 
             // 「ん」
@@ -412,31 +997,52 @@ impl<'t, 'd> Part<'t, 'd> {
                     ..
                 }) => match morphs.pop() {
                     Some(M {
-                        wordclass: W::Verb(_),
-                        basic: "ある",
+                        wordclass: W::Verb,
+                        basic,
                         ..
-                    }) => morphs_to_string(&morphs) + &fixlast("ない"),
+                    }) if basic == "ある" || basic == "ござる" => (
+                        if basic == "ある" {
+                            Rule::NegativeAru
+                        } else {
+                            Rule::Formal
+                        },
+                        vec![morphs_to_string(&morphs) + &fixlast("ない")],
+                    ),
+                    Some(M {
+                        wordclass: W::Verb,
+                        basic: "おる",
+                        ..
+                    }) => (
+                        Rule::Formal,
+                        vec![morphs_to_string(&morphs) + "い" + &fixlast("ない")],
+                    ),
                     Some(M {
                         surface,
                         conjugation: Conjugation { kind, form },
                         ..
-                    }) => {
-                        morphs_to_string(&morphs)
-                            + &fix(surface, kind, form, &[F::Negative])
-                            + &fixlast("ない")
-                    }
-                    None => "".into(),
+                    }) => (
+                        Rule::NegativeMasu,
+                        vec![
+                            morphs_to_string(&morphs)
+                                + &fix(surface, kind, form, &[F::Negative])
+                                + &fixlast("ない"),
+                        ],
+                    ),
+                    None => (Rule::NegativeMasu, vec!["".into()]),
                 },
                 Some(M {
                     surface,
                     conjugation: Conjugation { kind, form },
                     ..
-                }) => {
-                    morphs_to_string(&morphs)
-                        + &fix(surface, kind, form, &[F::Negative])
-                        + &fixlast("ない")
-                }
-                None => fixlast("ない"),
+                }) => (
+                    Rule::NegativeOther,
+                    vec![
+                        morphs_to_string(&morphs)
+                            + &fix(surface, kind, form, &[F::Negative])
+                            + &fixlast("ない"),
+                    ],
+                ),
+                None => (Rule::NegativeOther, vec![fixlast("ない")]),
             },
 
             // 過去の「た」
@@ -452,36 +1058,62 @@ impl<'t, 'd> Part<'t, 'd> {
                         ..
                     },
                 ) => {
+                    let alternative = morphs_to_string(&morphs) + "であった";
                     morphs.push(morph);
-                    Part::new(morphs).into_impolite(&[F::ContinuousTa, F::Continuous])
-                        + &fixlast("た")
+                    (
+                        Rule::PastDesu,
+                        vec![
+                            Part::new(morphs)
+                                .into_impolite(&[F::ContinuousTa, F::Continuous])
+                                .into_best()
+                                + &fixlast("た"),
+                            alternative,
+                        ],
+                    )
                 }
                 Some(M {
                     wordclass: W::AuxiliaryVerb,
                     basic: "ます",
                     ..
                 }) => match morphs.pop() {
+                    Some(M {
+                        wordclass: W::Verb,
+                        basic,
+                        ..
+                    }) if basic == "ござる" || basic == "おる" => (
+                        Rule::Formal,
+                        vec![
+                            plain_of_formal(&mut morphs, basic, &[F::ContinuousTa, F::Continuous])
+                                + &fixlast("た"),
+                        ],
+                    ),
                     Some(M {
                         surface,
                         conjugation: Conjugation { kind, form },
                         ..
-                    }) => {
-                        morphs_to_string(&morphs)
-                            + &fix(surface, kind, form, &[F::ContinuousTa, F::Continuous])
-                            + &fixlast("た")
-                    }
-                    None => fixlast("た"),
+                    }) => (
+                        Rule::PastMasu,
+                        vec![
+                            morphs_to_string(&morphs)
+                                + &fix(surface, kind, form, &[F::ContinuousTa, F::Continuous])
+                                + &fixlast("た"),
+                        ],
+                    ),
+                    None => (Rule::PastMasu, vec![fixlast("た")]),
                 },
                 Some(M {
                     surface,
                     conjugation: Conjugation { kind, form },
                     ..
-                }) => {
-                    morphs_to_string(&morphs)
-                        + &fix(surface, kind, form, &[F::ContinuousTa, F::Continuous])
-                        + &fixlast("た")
-                }
-                None => fixlast("た"),
+                }) => (
+                    Rule::PastOther,
+                    vec![
+                        morphs_to_string(&morphs)
+                            + &fix(surface, kind, form, &[F::ContinuousTa, F::Continuous])
+                            + &fixlast("た"),
+                    ],
+                ),
+                None => (Rule::PastOther, vec![fixlast("た")]),
             },
 
             // それ以外
@@ -489,17 +1121,20 @@ impl<'t, 'd> Part<'t, 'd> {
                 surface,
                 conjugation: Conjugation { kind, form },
                 ..
-            } => morphs_to_string(&morphs) + &fix(surface, kind, form, last_forms),
+            } => (
+                Rule::AlreadyPlain,
+                vec![morphs_to_string(&morphs) + &fix(surface, kind, form, last_forms)],
+            ),
         };
 
-        without_sep + &ends + sep_surface
+        Conversion::new(rule, without_sep, &(ends + sep_surface))
     }
 }
 
-fn take_ends<'t, 'd>(morphs: &mut Vec<Morpheme<'t, 'd>>) -> String {
-    use typed_igo::wordclass::Postpositional as P;
-    use typed_igo::Morpheme as M;
-    use typed_igo::WordClass as W;
+fn take_ends(morphs: &mut Vec<MorphemeRef>) -> String {
+    use crate::tokenizer::MorphemeRef as M;
+    use crate::tokenizer::Postpositional as P;
+    use crate::tokenizer::WordClass as W;
     let mut ends = Vec::new();
     loop {
         match morphs.pop() {
@@ -529,48 +1164,181 @@ fn take_ends<'t, 'd>(morphs: &mut Vec<Morpheme<'t, 'd>>) -> String {
     ends.into_iter().rev().collect()
 }
 
-fn morphs_to_string<'t, 'd>(morphs: &[Morpheme<'t, 'd>]) -> String {
+/// 末尾の終助詞とそれ以外に分ける。
+fn split_ends<'a, 'b>(
+    morphs: &'a [MorphemeRef<'b>],
+) -> (&'a [MorphemeRef<'b>], &'a [MorphemeRef<'b>]) {
+    use crate::tokenizer::Postpositional as P;
+    use crate::tokenizer::WordClass as W;
+
+    let body_len = morphs
+        .iter()
+        .rposition(|m| {
+            !matches!(
+                m.wordclass,
+                W::Postpositional(P::End) | W::Postpositional(P::SupplementaryParallelEnd)
+            )
+        })
+        .map_or(0, |idx| idx + 1);
+
+    morphs.split_at(body_len)
+}
+
+/// `orig` を `to` のうち活用できる最初の形に活用させる。活用できなければそのまま返す。
+fn conjugate(
+    orig: &str,
+    kind: crate::tokenizer::ConjugationKind,
+    from: ConjugationForm,
+    to: &[ConjugationForm],
+) -> String {
+    to.iter()
+        .find_map(|&to| conjugation::convert(orig, kind, from, to).ok())
+        .unwrap_or_else(|| orig.to_string())
+}
+
+/// 「ござる」「おる」(`verb`) を前の語とまとめて普通の形にし、`forms` のいずれかに活用させる。
+///
+/// - 「でござる」 : 「だ」
+/// - 形容詞 + 「ござる」 : 形容詞 (寒うござる -> 寒い)
+/// - それ以外の「ござる」 : 「ある」
+/// - 「おる」 : 「いる」
+fn plain_of_formal(morphs: &mut Vec<MorphemeRef>, verb: &str, forms: &[ConjugationForm]) -> String {
+    use crate::tokenizer::MorphemeRef as M;
+    use crate::tokenizer::WordClass as W;
+    use crate::tokenizer::{ConjugationForm as F, ConjugationKind as K};
+
+    if verb == "おる" {
+        // 一段の「いる」は終止形以外はどれも「い」
+        let plain = match forms.first() {
+            Some(F::Basic) => "いる",
+            _ => "い",
+        };
+        return morphs_to_string(morphs) + plain;
+    }
+
+    match morphs.pop() {
+        Some(M {
+            wordclass: W::AuxiliaryVerb,
+            basic: "だ",
+            ..
+        }) => morphs_to_string(morphs) + &conjugate("だ", K::SpecialDa, F::Basic, forms),
+        Some(M {
+            wordclass: W::Adjective,
+            basic,
+            conjugation: Conjugation { kind, .. },
+            ..
+        }) => morphs_to_string(morphs) + &conjugate(basic, kind, F::Basic, forms),
+        Some(other) => {
+            morphs.push(other);
+            morphs_to_string(morphs) + &conjugate("ある", K::GodanRaAru, F::Basic, forms)
+        }
+        None => conjugate("ある", K::GodanRaAru, F::Basic, forms),
+    }
+}
+
+/// 形容詞の終止形をウ音便にする。(高い -> 高う、美しい -> 美しゅう、寒い -> 寒う)
+fn u_onbin(surface: &str) -> Option<String> {
+    const A_TO_O: &[(char, char)] = &[
+        ('あ', 'お'),
+        ('か', 'こ'),
+        ('が', 'ご'),
+        ('さ', 'そ'),
+        ('ざ', 'ぞ'),
+        ('た', 'と'),
+        ('だ', 'ど'),
+        ('な', 'の'),
+        ('は', 'ほ'),
+        ('ば', 'ぼ'),
+        ('ぱ', 'ぽ'),
+        ('ま', 'も'),
+        ('や', 'よ'),
+        ('ら', 'ろ'),
+        ('わ', 'お'),
+    ];
+    const I_TO_YU: &[(char, &str)] = &[
+        ('い', "ゆ"),
+        ('き', "きゅ"),
+        ('ぎ', "ぎゅ"),
+        ('し', "しゅ"),
+        ('じ', "じゅ"),
+        ('ち', "ちゅ"),
+        ('に', "にゅ"),
+        ('ひ', "ひゅ"),
+        ('び', "びゅ"),
+        ('み', "みゅ"),
+        ('り', "りゅ"),
+    ];
+
+    // 「いい」は「よい」のウ音便
+    if surface == "いい" {
+        return Some("よう".to_string());
+    }
+
+    let stem = surface.strip_suffix('い')?;
+    let mut chars = stem.chars();
+    let last = chars.next_back()?;
+    let rest = chars.as_str();
+
+    // 漢字で終わる場合 (高い、寒い) は読みがわからないが、どの段でも「う」を付ければよい。
+    let onbin = if let Some(&(_, o)) = A_TO_O.iter().find(|&&(a, _)| a == last) {
+        format!("{}{}う", rest, o)
+    } else if let Some(&(_, yu)) = I_TO_YU.iter().find(|&&(i, _)| i == last) {
+        format!("{}{}う", rest, yu)
+    } else {
+        format!("{}う", stem)
+    };
+
+    Some(onbin)
+}
+
+fn morphs_to_string(morphs: &[MorphemeRef]) -> String {
     morphs.iter().map(|m| m.surface).collect()
 }
 
 fn make_continuous(basic: &str, surface: &str, conjugation: Conjugation) -> String {
+    use crate::tokenizer::{ConjugationForm as F, ConjugationKind as K};
     use conjugation::convert;
-    use typed_igo::conjugation::{ConjugationForm as F, ConjugationKind as K};
     let Conjugation { kind, form } = conjugation;
 
+    // 活用できなかった場合は元の形のまま残す。
     match kind {
-        K::SahenSuruConnected => convert(surface, kind, form, F::Negative)
-            .expect("failed to convert (sahen-suru connected)"),
+        K::SahenSuruConnected => {
+            convert(surface, kind, form, F::Negative).unwrap_or_else(|_| surface.to_string())
+        }
 
-        K::SahenZuruConnected => format!("{}じ", &basic[0..basic.len() - "ずる".len()]),
+        K::SahenZuruConnected => match basic.strip_suffix("ずる") {
+            Some(stem) => format!("{}じ", stem),
+            None => surface.to_string(),
+        },
 
         // FIXME: なにをすればいいんだ？何が 一段・ル なんだ？
         K::IchidanRu => basic.to_string(),
 
-        K::SpecialNai | K::SpecialTai => convert(surface, kind, form, F::ContinuousDe)
-            .expect("failed to convert (special nai/tai)"),
+        K::SpecialNai | K::SpecialTai => {
+            convert(surface, kind, form, F::ContinuousDe).unwrap_or_else(|_| surface.to_string())
+        }
 
-        _ => convert(surface, kind, form, F::Continuous).expect("failed to convert"),
+        _ => convert(surface, kind, form, F::Continuous).unwrap_or_else(|_| surface.to_string()),
     }
 }
 
-struct Splitter<'t, 'd, I> {
+struct Splitter<'a, I> {
     rest: I,
-    curr: Option<Morpheme<'t, 'd>>,
-    next: Option<Morpheme<'t, 'd>>,
-    parts: Vec<Part<'t, 'd>>,
-    part: Vec<Morpheme<'t, 'd>>,
+    curr: Option<MorphemeRef<'a>>,
+    next: Option<MorphemeRef<'a>>,
+    parts: Vec<Part<'a>>,
+    part: Vec<MorphemeRef<'a>>,
     paren_level: u32,
 }
 
-impl<'t, 'd, I> Splitter<'t, 'd, I>
+impl<'a, I> Splitter<'a, I>
 where
-    I: Iterator<Item = Morpheme<'t, 'd>>,
+    I: Iterator<Item = MorphemeRef<'a>>,
     I: FusedIterator,
 {
-    fn new<IntoIter>(orig: IntoIter) -> Splitter<'t, 'd, I>
+    fn new<IntoIter>(orig: IntoIter) -> Splitter<'a, I>
     where
-        IntoIter: IntoIterator<Item = Morpheme<'t, 'd>, IntoIter = I>,
+        IntoIter: IntoIterator<Item = MorphemeRef<'a>, IntoIter = I>,
     {
         let mut iter = orig.into_iter();
         let first = iter.next();
@@ -590,7 +1358,7 @@ where
         self.curr.is_none()
     }
 
-    fn step_once(&mut self) -> Option<Morpheme<'t, 'd>> {
+    fn step_once(&mut self) -> Option<MorphemeRef<'a>> {
         use std::mem::replace;
         replace(&mut self.curr, replace(&mut self.next, self.rest.next()))
     }
@@ -598,17 +1366,15 @@ where
     fn break_part(&mut self) {
         use std::mem::replace;
         let part = replace(&mut self.part, Vec::new());
-        let sep = self.step_once().expect("unexpected end");
-        self.parts.push(Part::with_sep(part, sep));
-    }
-
-    fn unwrap_curr(&self) -> &Morpheme<'t, 'd> {
-        self.curr.as_ref().expect("unwrap_curr() called on None")
+        if let Some(sep) = self.step_once() {
+            self.parts.push(Part::with_sep(part, sep));
+        }
     }
 
     fn push_curr(&mut self) {
-        let curr = self.step_once().expect("unexpected end of iterator");
-        self.part.push(curr);
+        if let Some(curr) = self.step_once() {
+            self.part.push(curr);
+        }
     }
 
     fn push_last(&mut self) {
@@ -618,7 +1384,7 @@ where
         }
     }
 
-    fn break_into_parts(&mut self) -> Vec<Part<'t, 'd>> {
+    fn break_into_parts(&mut self) -> Vec<Part<'a>> {
         while !self.is_finished() {
             self.handle_paren_count();
             if self.should_be_break() {
@@ -634,24 +1400,35 @@ where
     }
 
     fn handle_paren_count(&mut self) {
-        use typed_igo::wordclass::Symbol as S;
-        use typed_igo::WordClass as W;
-        match self.unwrap_curr().wordclass {
+        use crate::tokenizer::Symbol as S;
+        use crate::tokenizer::WordClass as W;
+        let curr = match &self.curr {
+            Some(curr) => curr,
+            None => return,
+        };
+
+        // 閉じ括弧が多すぎる場合は深さ 0 のままにしておく
+        match curr.wordclass {
             W::Symbol(S::OpenParen) => self.paren_level += 1,
-            W::Symbol(S::CloseParen) => self.paren_level -= 1,
+            W::Symbol(S::CloseParen) => self.paren_level = self.paren_level.saturating_sub(1),
             _ => {}
         }
     }
 
     fn should_be_break(&self) -> bool {
-        use typed_igo::wordclass::{Postpositional as P, Symbol as S};
-        use typed_igo::WordClass as W;
+        use crate::tokenizer::WordClass as W;
+        use crate::tokenizer::{Postpositional as P, Symbol as S};
         // 括弧深度が 1 以上の場合は引用または発言とみなし、何も変換しない。つまり区切る必要もない。
         if self.paren_level >= 1 {
             return false;
         }
 
-        match self.unwrap_curr().wordclass {
+        let curr = match &self.curr {
+            Some(curr) => curr,
+            None => return false,
+        };
+
+        match curr.wordclass {
             // 基本は句点での分割
             W::Symbol(S::Period) => true,
 
@@ -661,7 +1438,7 @@ where
             // - (OK) 確認したところ問題ありませんでした。
             // - (OK) 言ったからには実行します。
             // - (NG) 今日は良い天気だが明日は雨のようです。 (「今日は良い天気でしたが」にしたい)
-            W::Postpositional(P::Conjunction) => self.unwrap_curr().basic == "が",
+            W::Postpositional(P::Conjunction) => curr.basic == "が",
 
             // それ以外は切らない
             _ => false,
@@ -669,108 +1446,213 @@ where
     }
 }
 
-fn create_period(basic: &'static str) -> Morpheme<'static, 'static> {
-    use typed_igo::conjugation::{ConjugationForm as F, ConjugationKind as K};
-    use typed_igo::wordclass::Symbol as S;
-    use typed_igo::WordClass as W;
-    Morpheme {
+/// 句点で終わっていない文に補う句点。元の文字列にはないので位置は `!0` にしておく。
+fn create_period(basic: &'static str) -> MorphemeRef<'static> {
+    use crate::tokenizer::Symbol as S;
+    use crate::tokenizer::WordClass as W;
+    use crate::tokenizer::{ConjugationForm as F, ConjugationKind as K};
+    MorphemeRef {
         surface: basic,
+        basic,
         wordclass: W::Symbol(S::Period),
         conjugation: Conjugation {
             kind: K::None,
             form: F::None,
         },
-        basic,
-        reading: basic,
-        pronunciation: basic,
         start: !0,
     }
 }
 
-#[cfg(test)]
+/// テスト用の解析器。辞書の読み込みは重いので、一度だけ作ってすべてのテストで使い回す。
+#[cfg(all(test, feature = "igo"))]
+pub(crate) fn test_parser() -> &'static typed_igo::Parser {
+    static PARSER: std::sync::OnceLock<typed_igo::Parser> = std::sync::OnceLock::new();
+    PARSER.get_or_init(typed_igo::Parser::new)
+}
+
+#[cfg(all(test, feature = "igo"))]
 mod tests {
     use super::*;
 
-    lazy_static::lazy_static! {
-        static ref PARSER: Parser = Parser::new();
-    }
+    // 文ごとの変換結果は tests/corpus/*.tsv で確かめている。
 
-    macro_rules! check {
-        ($($testname:ident >> $from:literal => $to:literal => $inv:literal)*) => {
-            $(
-                #[test]
-                fn $testname() {
-                    assert_eq!(to_polite_sentence(&*PARSER, $from), $to);
-                    assert_eq!(to_impolite_sentence(&*PARSER, $to), $inv);
-                }
-            )*
+    #[test]
+    fn n_best() {
+        let candidates = |orig, target| -> Vec<Vec<String>> {
+            candidates(test_parser(), orig, target)
+                .into_iter()
+                .map(|c| c.candidates)
+                .collect()
+        };
+
+        assert_eq!(
+            candidates("今日は寒くなかった。今日は勉強をしよう。", Target::Polite),
+            [
+                ["今日は寒くありませんでした。", "今日は寒くなかったです。"],
+                ["今日は勉強をしましょう。", "今日は勉強をしようと思います。"],
+            ]
+        );
+        assert_eq!(
+            candidates("今日は晴天です。", Target::Impolite),
+            [["今日は晴天だ。", "今日は晴天である。"]]
+        );
+
+        let persona = casual::Persona {
+            copula_particle: "ね".to_string(),
+            ..casual::Persona::default()
         };
+        let casual = candidates_with(
+            test_parser(),
+            "今日は晴天です。",
+            &Options::new(Target::Casual).persona(persona),
+        );
+        assert_eq!(casual[0].candidates[0], "今日は晴天だね。");
     }
 
-    check! {
-        simple >>
-        "今日は晴天だ。"
-        => "今日は晴天です。"
-        => "今日は晴天だ。"
-
-        longer >>
-        "前進をしない人は、後退をしているのだ。"
-        => "前進をしない人は、後退をしているのです。"
-        => "前進をしない人は、後退をしているのだ。"
-
-        multiple_sentences >>
-        "どんなに悔いても過去は変わらない。どれほど心配したところで未来もどうなるものでもない。いま、現在に最善を尽くすことである。"
-        => "どんなに悔いても過去は変わりません。どれほど心配したところで未来もどうなるものでもありません。いま、現在に最善を尽くすことです。"
-        => "どんなに悔いても過去は変わらない。どれほど心配したところで未来もどうなるものでもない。いま、現在に最善を尽くすことだ。"
-
-        quote1 >>
-        "最も重要な決定とは、何をするかではなく、何をしないかを決めることだ。"
-        => "最も重要な決定とは、何をするかではなく、何をしないかを決めることです。"
-        => "最も重要な決定とは、何をするかではなく、何をしないかを決めることだ。"
-
-        quote2 >>
-        "数えきれないほど、悔しい思いをしてきたけれどその度にお袋の「我慢しなさい」って言葉を思い浮かべて、なんとか笑ってきたんです。"
-        => "数えきれないほど、悔しい思いをしてきたけれどその度にお袋の「我慢しなさい」って言葉を思い浮かべて、なんとか笑ってきたんです。"
-        => "数えきれないほど、悔しい思いをしてきたけれどその度にお袋の「我慢しなさい」って言葉を思い浮かべて、なんとか笑ってきたんだ。"
-
-        quote3 >>
-        "善人はこの世で多くの害をなす。彼らがなす最大の害は、人びとを善人と悪人に分けてしまうことだ。"
-        => "善人はこの世で多くの害をなします。彼らがなす最大の害は、人びとを善人と悪人に分けてしまうことです。"
-        => "善人はこの世で多くの害をなす。彼らがなす最大の害は、人びとを善人と悪人に分けてしまうことだ。"
-
-        adjective_past1 >>
-        "今日は寒かった。"
-        => "今日は寒かったです。"
-        => "今日は寒かった。"
-
-        end1 >>
-        "今日はいい天気か。"
-        => "今日はいい天気ですか。"
-        => "今日はいい天気か。"
-
-        lets1 >>
-        "今日は勉強をしよう。"
-        => "今日は勉強をしましょう。"
-        => "今日は勉強をしよう。"
-
-        nn >>
-        "許さん。"
-        => "許しません。"
-        => "許さない。"
-
-        already1 >>
-        "京都大学は1897年の創立以来、「自重自敬」の精神に基づき自由な学風を育み、創造的な学問の世界を切り開いてきました。また、地球社会の調和ある共存に貢献することも京都大学の重要な目標です。"
-        => "京都大学は1897年の創立以来、「自重自敬」の精神に基づき自由な学風を育み、創造的な学問の世界を切り開いてきました。また、地球社会の調和ある共存に貢献することも京都大学の重要な目標です。"
-        => "京都大学は1897年の創立以来、「自重自敬」の精神に基づき自由な学風を育み、創造的な学問の世界を切り開いてきた。また、地球社会の調和ある共存に貢献することも京都大学の重要な目標だ。"
-
-        already2 >>
-        "一方で今、世界は20世紀には想像もしなかったような急激な変化を体験しつつあります。東西冷戦の終結によって解消するはずだった世界の対立構造は、民族間、宗教間の対立によってますます複雑かつ過酷になっています。他方、地球環境の悪化は加速し、想定外の大規模な災害や致死性の感染症が各地で猛威をふるい、金融危機は国の経済や人々の生活を根本から揺さぶっています。その荒波の中で、大学はどうあるべきかを真摯に考えて行かなければなりません。そして、国は産官学連携を推進してグローバルに活躍できる人材育成を奨励し、国際的に競争力のある大学改革を要請しています。京都大学が建学の精神に立ちつつ、どのようにこの国や社会の要請にこたえていけるかが今問われています。"
-        => "一方で今、世界は20世紀には想像もしなかったような急激な変化を体験しつつあります。東西冷戦の終結によって解消するはずだった世界の対立構造は、民族間、宗教間の対立によってますます複雑かつ過酷になっています。他方、地球環境の悪化は加速し、想定外の大規模な災害や致死性の感染症が各地で猛威をふるい、金融危機は国の経済や人々の生活を根本から揺さぶっています。その荒波の中で、大学はどうあるべきかを真摯に考えて行かなければなりません。そして、国は産官学連携を推進してグローバルに活躍できる人材育成を奨励し、国際的に競争力のある大学改革を要請しています。京都大学が建学の精神に立ちつつ、どのようにこの国や社会の要請にこたえていけるかが今問われています。"
-        => "一方で今、世界は20世紀には想像もしなかったような急激な変化を体験しつつある。東西冷戦の終結によって解消するはずだった世界の対立構造は、民族間、宗教間の対立によってますます複雑かつ過酷になっている。他方、地球環境の悪化は加速し、想定外の大規模な災害や致死性の感染症が各地で猛威をふるい、金融危機は国の経済や人々の生活を根本から揺さぶっている。その荒波の中で、大学はどうあるべきかを真摯に考えて行かなければならない。そして、国は産官学連携を推進してグローバルに活躍できる人材育成を奨励し、国際的に競争力のある大学改革を要請している。京都大学が建学の精神に立ちつつ、どのようにこの国や社会の要請にこたえていけるかが今問われている。"
-
-        longlong >>
-        "2019年現在、定期列車は大阪駅-金沢駅間で25往復が運転されている。うち1往復は和倉温泉駅まで延長運転されている。所要時間は大阪駅-金沢駅間が2時間35-40分である。最速列車が下り37号（2時間31分）で、表定速度が日本最速である。全列車が湖西線経由で大阪駅を発着として運転されるが、強風などで湖西線が運転見合わせになった場合は、米原駅経由で迂回運転される。米原駅では原則として運転停車だが、事情により客扱いをすることもある。2000年代に入ってからは比良おろしとよばれる強風による運転規制の強化により迂回運転が増えていたが、防風柵の設置工事により迂回運転は減少するとしている。迂回運転による所要時間の増加は約30分だが、折り返しとなる列車がさらに遅れる場合も多い。風が小康状態となり、かつ運転規制が解除されると湖西線経由に戻される。なお、何らかの理由で湖西線が不通になった事態を想定して、米原駅経由のダイヤもあらかじめ設定されている。なお北陸新幹線金沢開業以前の2015年3月13日までは、14往復が大阪駅-富山駅間、1往復が大阪駅-魚津駅間での運行であり、大阪駅-富山駅間の平均所要時間は3時間20分であった。富山駅・魚津駅発着系統は増結により12両編成で運転される場合、列車によっては金沢駅で1-9号車と10-12号車の増解結を行うことがあった。"
-        => "2019年現在、定期列車は大阪駅-金沢駅間で25往復が運転されています。うち1往復は和倉温泉駅まで延長運転されています。所要時間は大阪駅-金沢駅間が2時間35-40分です。最速列車が下り37号（2時間31分）で、表定速度が日本最速です。全列車が湖西線経由で大阪駅を発着として運転されますが、強風などで湖西線が運転見合わせになった場合は、米原駅経由で迂回運転されます。米原駅では原則として運転停車ですが、事情により客扱いをすることもあります。2000年代に入ってからは比良おろしとよばれる強風による運転規制の強化により迂回運転が増えていましたが、防風柵の設置工事により迂回運転は減少するとしています。迂回運転による所要時間の増加は約30分ですが、折り返しとなる列車がさらに遅れる場合も多いです。風が小康状態となり、かつ運転規制が解除されると湖西線経由に戻されます。なお、何らかの理由で湖西線が不通になった事態を想定して、米原駅経由のダイヤもあらかじめ設定されています。なお北陸新幹線金沢開業以前の2015年3月13日までは、14往復が大阪駅-富山駅間、1往復が大阪駅-魚津駅間での運行であり、大阪駅-富山駅間の平均所要時間は3時間20分でした。富山駅・魚津駅発着系統は増結により12両編成で運転される場合、列車によっては金沢駅で1-9号車と10-12号車の増解結を行うことがありました。"
-        => "2019年現在、定期列車は大阪駅-金沢駅間で25往復が運転されている。うち1往復は和倉温泉駅まで延長運転されている。所要時間は大阪駅-金沢駅間が2時間35-40分だ。最速列車が下り37号（2時間31分）で、表定速度が日本最速だ。全列車が湖西線経由で大阪駅を発着として運転されるが、強風などで湖西線が運転見合わせになった場合は、米原駅経由で迂回運転される。米原駅では原則として運転停車だが、事情により客扱いをすることもある。2000年代に入ってからは比良おろしとよばれる強風による運転規制の強化により迂回運転が増えていたが、防風柵の設置工事により迂回運転は減少するとしている。迂回運転による所要時間の増加は約30分だが、折り返しとなる列車がさらに遅れる場合も多い。風が小康状態となり、かつ運転規制が解除されると湖西線経由に戻される。なお、何らかの理由で湖西線が不通になった事態を想定して、米原駅経由のダイヤもあらかじめ設定されている。なお北陸新幹線金沢開業以前の2015年3月13日までは、14往復が大阪駅-富山駅間、1往復が大阪駅-魚津駅間での運行であり、大阪駅-富山駅間の平均所要時間は3時間20分だった。富山駅・魚津駅発着系統は増結により12両編成で運転される場合、列車によっては金沢駅で1-9号車と10-12号車の増解結を行うことがあった。"
+    #[test]
+    fn conservative() {
+        let orig = "今日は晴天だ。今日は寒くなかった。インストール方法";
+        let options = Options::conservative(Target::Polite);
+
+        let results = convert_sentences(test_parser(), orig, &options);
+        let summary: Vec<_> = results
+            .iter()
+            .map(|r| (r.converted.as_str(), r.confidence, r.needs_review))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("今日は晴天です。", Confidence::High, false),
+                ("今日は寒くありませんでした。", Confidence::High, false),
+                ("インストール方法", Confidence::Medium, true),
+            ]
+        );
+        assert_eq!(results[2].rules, [Rule::AppendDesu]);
+
+        assert_eq!(
+            convert_with(test_parser(), orig, &options),
+            "今日は晴天です。今日は寒くありませんでした。インストール方法"
+        );
+    }
+
+    #[test]
+    fn formal() {
+        let formal = |orig| to_politeness_level(test_parser(), orig, PolitenessLevel::Formal);
+        assert_eq!(formal("今日は晴天だ。"), "今日は晴天でございます。");
+        assert_eq!(formal("今日は晴天です。"), "今日は晴天でございます。");
+        assert_eq!(formal("ここに本がある。"), "ここに本がございます。");
+        assert_eq!(formal("駅で待っている。"), "駅で待っております。");
+        assert_eq!(formal("今日は寒い。"), "今日は寒うございます。");
+        assert_eq!(
+            formal("とても美味しいです。"),
+            "とても美味しゅうございます。"
+        );
+        assert_eq!(formal("明日は行く。"), "明日は行きます。");
+
+        let results = convert_sentences(
+            test_parser(),
+            "今日は晴天だ。駅で待っている。",
+            &Options::conservative(Target::Formal),
+        );
+        assert_eq!(results[0].converted, "今日は晴天でございます。");
+        assert_eq!(results[0].rules, [Rule::FormalCopula]);
+        assert_eq!(results[1].rules, [Rule::FormalIru]);
+        assert!(results[1].needs_review);
+
+        let plain = |orig| to_politeness_level(test_parser(), orig, PolitenessLevel::Plain);
+        assert_eq!(plain("今日は晴天でございます。"), "今日は晴天だ。");
+        assert_eq!(plain("ここに本がございます。"), "ここに本がある。");
+        assert_eq!(plain("駅で待っております。"), "駅で待っている。");
+        assert_eq!(plain("今日は寒うございます。"), "今日は寒い。");
+        assert_eq!(plain("昨日は寒うございました。"), "昨日は寒かった。");
+        assert_eq!(plain("ここに本はございません。"), "ここに本はない。");
+    }
+
+    #[test]
+    fn u_onbin_forms() {
+        assert_eq!(u_onbin("高い").as_deref(), Some("高う"));
+        assert_eq!(u_onbin("美しい").as_deref(), Some("美しゅう"));
+        assert_eq!(u_onbin("おいしい").as_deref(), Some("おいしゅう"));
+        assert_eq!(u_onbin("ありがたい").as_deref(), Some("ありがとう"));
+        assert_eq!(u_onbin("いい").as_deref(), Some("よう"));
+        assert_eq!(u_onbin("寒かっ"), None);
+    }
+
+    /// どんな入力でも panic しないことの確認。
+    #[cfg(not(target_arch = "wasm32"))]
+    mod never_panics {
+        use super::*;
+        use proptest::prelude::*;
+
+        /// ランダムな形態素列の材料。一通りの品詞や活用が出てくるようにしている。
+        const SEED: &str = "今日は晴天だ。前進をしない人は、後退をしているのだ。許さん。今日は勉強をしよう。\
+                            お袋の「我慢しなさい」って言葉（2時間31分）が、寒くなかったです。行きません。\
+                            論ずるところである。食べたいか。ありました。でしょう。";
+
+        fn seed_indices() -> impl Strategy<Value = Vec<usize>> {
+            let len = test_parser().tokenize(SEED).len();
+            proptest::sample::subsequence((0..len).collect::<Vec<_>>(), 0..=len).prop_shuffle()
+        }
+
+        fn convert_morphemes(indices: &[usize], target: Target) -> String {
+            use crate::tokenizer::ConjugationForm as F;
+
+            let pool = test_parser().tokenize(SEED);
+            let parts = indices
+                .iter()
+                .map(|&i| pool[i].view())
+                .transform(Splitter::new)
+                .break_into_parts();
+            parts
+                .into_iter()
+                .map(|part| match target {
+                    Target::Polite => part.into_polite(F::Basic).into_best(),
+                    Target::Impolite | Target::Casual => {
+                        part.into_impolite(&[F::Basic]).into_best()
+                    }
+                    Target::Formal => part.into_formal().into_best(),
+                })
+                .collect()
+        }
+
+        proptest! {
+            #[test]
+            fn arbitrary_text_never_panics(text in "\\PC*") {
+                to_polite_sentence(test_parser(), &text);
+                to_impolite_sentence(test_parser(), &text);
+            }
+
+            #[test]
+            fn japanese_text_never_panics(text in "[ぁ-んァ-ヶ一-龠ー。、！？「」（）]{0,50}") {
+                to_polite_sentence(test_parser(), &text);
+                to_impolite_sentence(test_parser(), &text);
+            }
+
+            #[test]
+            fn documents_never_panic(text in "[ぁ-ん一-龠。「」（）<>/b#|` \n-]{0,80}") {
+                for &target in &[Target::Polite, Target::Impolite] {
+                    edit::edits(test_parser(), &text, target);
+                    markdown::convert_markdown(
+                        test_parser(),
+                        &text,
+                        target,
+                        &markdown::MarkdownPolicy::default(),
+                    );
+                    html::convert_html(
+                        test_parser(),
+                        &text,
+                        target,
+                        &markdown::MarkdownPolicy::default(),
+                    );
+                }
+            }
+
+            #[test]
+            fn morpheme_sequences_never_panic(indices in seed_indices()) {
+                convert_morphemes(&indices, Target::Polite);
+                convert_morphemes(&indices, Target::Impolite);
+                convert_morphemes(&indices, Target::Formal);
+            }
+        }
     }
 }
@@ -0,0 +1,65 @@
+//! lindera による形態素解析。
+//!
+//! IPADIC の辞書で作った `Segmenter` をそのまま `Tokenizer` として使える。
+//!
+//! ```ignore
+//! use lindera::dictionary::load_dictionary;
+//! use lindera::mode::Mode;
+//! use lindera::segmenter::Segmenter;
+//!
+//! let dictionary = load_dictionary("embedded://ipadic")?;
+//! let segmenter = Segmenter::new(Mode::Normal, dictionary, None);
+//! to_polite::to_polite_sentence(&segmenter, "今日は晴天だ。");
+//! ```
+
+use crate::ipadic;
+use crate::tokenizer::{Morpheme, Tokenizer};
+use lindera::segmenter::Segmenter;
+use std::borrow::Cow;
+
+impl Tokenizer for Segmenter {
+    fn tokenize(&self, text: &str) -> Vec<Morpheme> {
+        let mut tokens = match self.segment(Cow::Borrowed(text)) {
+            Ok(tokens) => tokens,
+            // 解析できなかった場合は全体を一つの未知語として、変換せずに残す。
+            Err(_) => return vec![ipadic::morpheme(text, 0, &[])],
+        };
+
+        tokens
+            .iter_mut()
+            .map(|token| {
+                let start = token.byte_start;
+                let surface = token.surface.to_string();
+                ipadic::morpheme(&surface, start, &token.details())
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{to_impolite_sentence, to_polite_sentence};
+    use lindera::dictionary::load_dictionary;
+    use lindera::mode::Mode;
+
+    lazy_static::lazy_static! {
+        static ref SEGMENTER: Segmenter = Segmenter::new(
+            Mode::Normal,
+            load_dictionary("embedded://ipadic").expect("failed to load IPADIC"),
+            None,
+        );
+    }
+
+    #[test]
+    fn convert() {
+        assert_eq!(
+            to_polite_sentence(&*SEGMENTER, "今日は寒くなかった。"),
+            "今日は寒くありませんでした。"
+        );
+        assert_eq!(
+            to_impolite_sentence(&*SEGMENTER, "今日は勉強をしましょう。"),
+            "今日は勉強をしよう。"
+        );
+    }
+}
@@ -0,0 +1,127 @@
+//! 文体の混在の検出。
+//!
+//! 目標の文体と異なる文を見つけ、目標の文体にするための置換とともに返す。体言止めなどで文体が判定できない文は
+//! 対象にしない。
+
+use crate::edit::{self, Edit};
+use crate::style::{self, Style};
+use crate::tokenizer::Tokenizer;
+use crate::{convert_with, Options, Target};
+use std::ops::Range;
+
+/// 目標の文体と異なる文。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Problem {
+    /// 文の範囲 (バイト位置)
+    pub sentence: Range<usize>,
+    /// 文の文体
+    pub style: Style,
+    /// 目標の文体にするための置換
+    pub edit: Edit,
+}
+
+impl Problem {
+    pub fn message(&self) -> &'static str {
+        match self.style {
+            Style::Polite => "ですます調の文です。",
+            Style::Impolite => "である調の文です。",
+            Style::Mixed | Style::Unknown => "ですます調とである調が混ざっています。",
+        }
+    }
+
+    /// 文の置換前と置換後を含めた説明。`orig` は `lint()` に渡した文字列。
+    ///
+    /// 置換は「です」を足すだけのように元の部分が空のことがあるので、置換した部分ではなく文全体を示す。
+    pub fn describe(&self, orig: &str) -> String {
+        let sentence = &orig[self.sentence.clone()];
+        let edit = Edit {
+            range: self.edit.range.start - self.sentence.start
+                ..self.edit.range.end - self.sentence.start,
+            replacement: self.edit.replacement.clone(),
+        };
+        format!(
+            "{} (「{}」→「{}」)",
+            self.message(),
+            sentence,
+            edit::apply(sentence, &[edit])
+        )
+    }
+}
+
+/// 文の数が多い方の文体に変換する向き。同数ならですます調。文体を判定できる文がなければ `None` 。
+pub fn majority(parser: &dyn Tokenizer, orig: &str) -> Option<Target> {
+    let (mut polite, mut impolite) = (0, 0);
+    for range in edit::sentence_ranges(orig) {
+        match style::detect_style(parser, &orig[range]) {
+            Style::Polite => polite += 1,
+            Style::Impolite => impolite += 1,
+            Style::Mixed | Style::Unknown => {}
+        }
+    }
+
+    if polite == 0 && impolite == 0 {
+        None
+    } else if polite >= impolite {
+        Some(Target::Polite)
+    } else {
+        Some(Target::Impolite)
+    }
+}
+
+pub fn lint(parser: &dyn Tokenizer, orig: &str, target: Target) -> Vec<Problem> {
+    lint_with(parser, orig, &Options::new(target))
+}
+
+/// `options` の文体と異なる文を返す。置換は `options` に従って変換したもので、確信度が足りなければ報告しない。
+pub fn lint_with(parser: &dyn Tokenizer, orig: &str, options: &Options) -> Vec<Problem> {
+    let expected = match options.target {
+        Target::Polite | Target::Formal => Style::Polite,
+        Target::Impolite | Target::Casual => Style::Impolite,
+    };
+
+    edit::sentence_ranges(orig)
+        .into_iter()
+        .filter_map(|range| {
+            let sentence = &orig[range.clone()];
+            let style = style::detect_style(parser, sentence);
+            if style == expected || style == Style::Unknown {
+                return None;
+            }
+
+            let converted = convert_with(parser, sentence, options);
+            let edit = edit::minimal_edit(sentence, &converted)?;
+            Some(Problem {
+                edit: Edit {
+                    range: range.start + edit.range.start..range.start + edit.range.end,
+                    replacement: edit.replacement,
+                },
+                sentence: range,
+                style,
+            })
+        })
+        .collect()
+}
+
+#[cfg(all(test, feature = "igo"))]
+mod tests {
+    use super::*;
+    use crate::test_parser;
+
+    #[test]
+    fn mixed_styles() {
+        let orig = "今日は晴天です。今日は寒かった。インストール方法";
+        let problems = lint(test_parser(), orig, Target::Polite);
+        assert_eq!(problems.len(), 1);
+        assert_eq!(&orig[problems[0].sentence.clone()], "今日は寒かった。");
+        assert_eq!(problems[0].style, Style::Impolite);
+        assert_eq!(
+            edit::apply(orig, &[problems[0].edit.clone()]),
+            "今日は晴天です。今日は寒かったです。インストール方法"
+        );
+        assert_eq!(
+            problems[0].describe(orig),
+            "である調の文です。 (「今日は寒かった。」→「今日は寒かったです。」)"
+        );
+        assert_eq!(majority(test_parser(), orig), Some(Target::Polite));
+    }
+}
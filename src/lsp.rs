@@ -0,0 +1,344 @@
+//! Language Server Protocol のサーバー。
+//!
+//! 開いている文書で目標の文体と異なる文に診断を出し、「ですます調に変換」「である調に変換」のコードアクションを
+//! 返す。目標の文体は `initializationOptions` に `{"target": "impolite"}` のように指定する (既定はですます調)。
+//! 話し言葉 (`"casual"`) の話し方は `{"target": "casual", "persona": {"copula_particle": "ね"}}` のように指定する。
+//! 文書の同期は差分で受け取る。位置は LSP の既定どおり UTF-16 で数える。
+
+use crate::casual::Persona;
+use crate::lint;
+use crate::tokenizer::Tokenizer;
+use crate::{edit, Options, Target};
+use lsp_server::{Connection, ErrorCode, Message, Notification, Request, Response};
+use lsp_types::notification::{
+    DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument,
+    Notification as LspNotification, PublishDiagnostics,
+};
+use lsp_types::request::{CodeActionRequest, Request as LspRequest};
+use lsp_types::{
+    CodeAction, CodeActionKind, CodeActionOrCommand, CodeActionParams,
+    CodeActionProviderCapability, Diagnostic, DiagnosticSeverity, DidChangeTextDocumentParams,
+    DidCloseTextDocumentParams, DidOpenTextDocumentParams, Position, PublishDiagnosticsParams,
+    Range, ServerCapabilities, TextDocumentContentChangeEvent, TextDocumentSyncCapability,
+    TextDocumentSyncKind, TextEdit, Url, WorkspaceEdit,
+};
+use std::collections::HashMap;
+use std::error::Error;
+
+/// `connection` で初期化から終了までを処理する。
+pub fn run(
+    connection: &Connection,
+    parser: &dyn Tokenizer,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let capabilities = ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(
+            TextDocumentSyncKind::INCREMENTAL,
+        )),
+        code_action_provider: Some(CodeActionProviderCapability::Simple(true)),
+        ..ServerCapabilities::default()
+    };
+    let params = connection.initialize(serde_json::to_value(capabilities)?)?;
+    let init = params.get("initializationOptions");
+    let target = match init
+        .and_then(|options| options.get("target"))
+        .and_then(|target| target.as_str())
+    {
+        Some("impolite") => Target::Impolite,
+        Some("casual") => Target::Casual,
+        Some("formal") => Target::Formal,
+        _ => Target::Polite,
+    };
+    let persona = match init.and_then(|options| options.get("persona")) {
+        Some(persona) => serde_json::from_value::<Persona>(persona.clone())?,
+        None => Persona::default(),
+    };
+
+    let mut server = Server {
+        parser,
+        options: Options::new(target).persona(persona),
+        documents: HashMap::new(),
+    };
+
+    for message in &connection.receiver {
+        match message {
+            Message::Request(request) => {
+                if connection.handle_shutdown(&request)? {
+                    return Ok(());
+                }
+                let response = server.handle_request(request);
+                connection.sender.send(Message::Response(response))?;
+            }
+            Message::Notification(notification) => {
+                if let Some(uri) = server.handle_notification(notification) {
+                    let diagnostics = server.diagnostics(&uri);
+                    connection.sender.send(Message::Notification(diagnostics))?;
+                }
+            }
+            Message::Response(_) => {}
+        }
+    }
+
+    Ok(())
+}
+
+struct Server<'p> {
+    parser: &'p dyn Tokenizer,
+    options: Options,
+    documents: HashMap<Url, String>,
+}
+
+impl Server<'_> {
+    /// 文書の変更を反映する。診断を出し直す必要があれば、その文書の URI を返す。
+    fn handle_notification(&mut self, notification: Notification) -> Option<Url> {
+        match notification.method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                let params: DidOpenTextDocumentParams =
+                    serde_json::from_value(notification.params).ok()?;
+                let uri = params.text_document.uri;
+                self.documents
+                    .insert(uri.clone(), params.text_document.text);
+                Some(uri)
+            }
+            DidChangeTextDocument::METHOD => {
+                let params: DidChangeTextDocumentParams =
+                    serde_json::from_value(notification.params).ok()?;
+                let uri = params.text_document.uri;
+                let text = self.documents.get_mut(&uri)?;
+                for change in params.content_changes {
+                    apply_change(text, change);
+                }
+                Some(uri)
+            }
+            DidCloseTextDocument::METHOD => {
+                // 閉じた文書の診断は消す
+                let params: DidCloseTextDocumentParams =
+                    serde_json::from_value(notification.params).ok()?;
+                self.documents.remove(&params.text_document.uri);
+                Some(params.text_document.uri)
+            }
+            _ => None,
+        }
+    }
+
+    fn handle_request(&self, request: Request) -> Response {
+        if request.method != CodeActionRequest::METHOD {
+            return Response::new_err(
+                request.id,
+                ErrorCode::MethodNotFound as i32,
+                format!("unknown method: {}", request.method),
+            );
+        }
+
+        match serde_json::from_value::<CodeActionParams>(request.params) {
+            Ok(params) => Response::new_ok(request.id, self.code_actions(&params)),
+            Err(e) => Response::new_err(request.id, ErrorCode::InvalidParams as i32, e.to_string()),
+        }
+    }
+
+    fn diagnostics(&self, uri: &Url) -> Notification {
+        let diagnostics = match self.documents.get(uri) {
+            Some(text) => lint::lint_with(self.parser, text, &self.options)
+                .into_iter()
+                .map(|problem| Diagnostic {
+                    // 置換は「です」を足すだけなど幅がないことがあるので、文全体に付ける。
+                    // 置換はコードアクションで行う。
+                    range: range(text, &problem.sentence),
+                    severity: Some(DiagnosticSeverity::WARNING),
+                    source: Some("to-polite".to_string()),
+                    message: problem.describe(text),
+                    ..Diagnostic::default()
+                })
+                .collect(),
+            None => Vec::new(),
+        };
+
+        Notification::new(
+            PublishDiagnostics::METHOD.to_string(),
+            PublishDiagnosticsParams {
+                uri: uri.clone(),
+                diagnostics,
+                version: None,
+            },
+        )
+    }
+
+    /// 選択範囲にかかる文を変換するコードアクション。
+    fn code_actions(&self, params: &CodeActionParams) -> Vec<CodeActionOrCommand> {
+        let uri = &params.text_document.uri;
+        let text = match self.documents.get(uri) {
+            Some(text) => text,
+            None => return Vec::new(),
+        };
+
+        let start = offset(text, params.range.start);
+        let end = offset(text, params.range.end);
+        let sentences: Vec<_> = edit::sentence_ranges(text)
+            .into_iter()
+            .filter(|sentence| sentence.start <= end && start <= sentence.end)
+            .collect();
+
+        [
+            (Target::Polite, "ですます調に変換"),
+            (Target::Impolite, "である調に変換"),
+        ]
+        .iter()
+        .filter_map(|&(target, title)| {
+            let edits: Vec<TextEdit> = edit::edits(self.parser, text, target)
+                .into_iter()
+                .filter(|e| {
+                    sentences
+                        .iter()
+                        .any(|s| s.start <= e.range.start && e.range.end <= s.end)
+                })
+                .map(|e| TextEdit::new(range(text, &e.range), e.replacement))
+                .collect();
+            if edits.is_empty() {
+                return None;
+            }
+
+            let mut changes = HashMap::new();
+            changes.insert(uri.clone(), edits);
+            Some(CodeActionOrCommand::CodeAction(CodeAction {
+                title: title.to_string(),
+                kind: Some(CodeActionKind::QUICKFIX),
+                edit: Some(WorkspaceEdit {
+                    changes: Some(changes),
+                    ..WorkspaceEdit::default()
+                }),
+                ..CodeAction::default()
+            }))
+        })
+        .collect()
+    }
+}
+
+fn apply_change(text: &mut String, change: TextDocumentContentChangeEvent) {
+    match change.range {
+        Some(range) => {
+            let start = offset(text, range.start);
+            let end = offset(text, range.end).max(start);
+            text.replace_range(start..end, &change.text);
+        }
+        None => *text = change.text,
+    }
+}
+
+fn range(text: &str, range: &std::ops::Range<usize>) -> Range {
+    Range::new(position(text, range.start), position(text, range.end))
+}
+
+/// バイト位置を LSP の位置 (行と UTF-16 の桁) にする。
+fn position(text: &str, pos: usize) -> Position {
+    let line_start = text[..pos].rfind('\n').map_or(0, |idx| idx + 1);
+    let line = text[..line_start].matches('\n').count();
+    let character = text[line_start..pos].encode_utf16().count();
+    Position::new(line as u32, character as u32)
+}
+
+/// LSP の位置をバイト位置にする。行や文書の外を指している場合は行末や文書の末尾にする。
+fn offset(text: &str, position: Position) -> usize {
+    let mut line_start = 0;
+    for _ in 0..position.line {
+        match text[line_start..].find('\n') {
+            Some(idx) => line_start += idx + 1,
+            None => return text.len(),
+        }
+    }
+
+    let line_end = text[line_start..]
+        .find('\n')
+        .map_or(text.len(), |idx| line_start + idx);
+    let mut character = 0;
+    for (idx, ch) in text[line_start..line_end].char_indices() {
+        if character >= position.character as usize {
+            return line_start + idx;
+        }
+        character += ch.len_utf16();
+    }
+
+    line_end
+}
+
+#[cfg(all(test, feature = "igo"))]
+mod tests {
+    use super::*;
+    use crate::test_parser;
+    use lsp_server::RequestId;
+    use serde_json::json;
+    use std::thread;
+
+    #[test]
+    fn positions() {
+        let text = "今日は晴天だ。\n𠮷野家に行く。";
+        let pos = text.find("行く").unwrap();
+        assert_eq!(position(text, pos), Position::new(1, 5));
+        assert_eq!(offset(text, Position::new(1, 5)), pos);
+        assert_eq!(
+            offset(text, Position::new(0, 100)),
+            text.find('\n').unwrap()
+        );
+        assert_eq!(offset(text, Position::new(5, 0)), text.len());
+    }
+
+    #[test]
+    fn incremental_change() {
+        let mut text = "今日は晴天だ。\n明日は雨だ。".to_string();
+        apply_change(
+            &mut text,
+            TextDocumentContentChangeEvent {
+                range: Some(Range::new(Position::new(1, 3), Position::new(1, 4))),
+                range_length: None,
+                text: "雪".to_string(),
+            },
+        );
+        assert_eq!(text, "今日は晴天だ。\n明日は雪だ。");
+    }
+
+    #[test]
+    fn publish_diagnostics() {
+        let (server, client) = Connection::memory();
+        let handle = thread::spawn(move || run(&server, test_parser()).unwrap());
+
+        let request = Request::new(
+            RequestId::from(1),
+            "initialize".to_string(),
+            json!({ "capabilities": {}, "initializationOptions": { "target": "polite" } }),
+        );
+        client.sender.send(request.into()).unwrap();
+        client.receiver.recv().unwrap();
+        let initialized = Notification::new("initialized".to_string(), json!({}));
+        client.sender.send(initialized.into()).unwrap();
+
+        let open = Notification::new(
+            DidOpenTextDocument::METHOD.to_string(),
+            json!({
+                "textDocument": {
+                    "uri": "file:///a.md",
+                    "languageId": "markdown",
+                    "version": 1,
+                    "text": "今日は晴天です。\n今日は寒かった。",
+                }
+            }),
+        );
+        client.sender.send(open.into()).unwrap();
+
+        let params = match client.receiver.recv().unwrap() {
+            Message::Notification(notification) => notification
+                .extract::<PublishDiagnosticsParams>(PublishDiagnostics::METHOD)
+                .unwrap(),
+            message => panic!("unexpected message: {:?}", message),
+        };
+        assert_eq!(params.diagnostics.len(), 1);
+        assert_eq!(
+            params.diagnostics[0].range,
+            Range::new(Position::new(1, 0), Position::new(1, 8))
+        );
+
+        let shutdown = Request::new(RequestId::from(2), "shutdown".to_string(), json!(null));
+        client.sender.send(shutdown.into()).unwrap();
+        client.receiver.recv().unwrap();
+        let exit = Notification::new("exit".to_string(), json!(null));
+        client.sender.send(exit.into()).unwrap();
+        handle.join().unwrap();
+    }
+}
@@ -0,0 +1,456 @@
+//! Markdown 文書の変換。
+//!
+//! 見出しや箇条書きは体言止めのことが多く、そのまま変換すると「インストール方法です」のようになってしまう。
+//! そこでブロックの種類ごとに変換するかどうかの方針を指定できるようにしている。コードブロックとインラインのコードは
+//! 常に変換しない。
+
+use crate::edit::{self, Edit, Run};
+use crate::tokenizer::Tokenizer;
+use crate::Target;
+use std::ops::Range;
+
+/// ブロックの種類。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockKind {
+    Heading,
+    ListItem,
+    TableCell,
+    Paragraph,
+}
+
+/// ブロックに対する変換の方針。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockPolicy {
+    /// 常に変換する。
+    Convert,
+    /// 変換しない。
+    Skip,
+    /// すでに文として完結している (用言で終わっている) 場合だけ変換する。
+    SentenceOnly,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MarkdownPolicy {
+    pub heading: BlockPolicy,
+    pub list_item: BlockPolicy,
+    pub table_cell: BlockPolicy,
+    pub paragraph: BlockPolicy,
+}
+
+impl Default for MarkdownPolicy {
+    fn default() -> MarkdownPolicy {
+        MarkdownPolicy {
+            heading: BlockPolicy::Skip,
+            list_item: BlockPolicy::SentenceOnly,
+            table_cell: BlockPolicy::Skip,
+            paragraph: BlockPolicy::Convert,
+        }
+    }
+}
+
+impl MarkdownPolicy {
+    pub fn get(&self, kind: BlockKind) -> BlockPolicy {
+        match kind {
+            BlockKind::Heading => self.heading,
+            BlockKind::ListItem => self.list_item,
+            BlockKind::TableCell => self.table_cell,
+            BlockKind::Paragraph => self.paragraph,
+        }
+    }
+}
+
+pub fn to_polite_markdown(parser: &dyn Tokenizer, orig: &str, policy: &MarkdownPolicy) -> String {
+    convert_markdown(parser, orig, Target::Polite, policy)
+}
+
+pub fn to_impolite_markdown(parser: &dyn Tokenizer, orig: &str, policy: &MarkdownPolicy) -> String {
+    convert_markdown(parser, orig, Target::Impolite, policy)
+}
+
+pub fn convert_markdown(
+    parser: &dyn Tokenizer,
+    orig: &str,
+    target: Target,
+    policy: &MarkdownPolicy,
+) -> String {
+    let mut result = String::with_capacity(orig.len());
+    // 段落は複数行にまたがるので、段落が終わるまで (行頭のマークアップ, 本文, 改行) をためておく。
+    let mut paragraph: Vec<(&str, &str, &str)> = Vec::new();
+    let mut fence: Option<&str> = None;
+
+    let flush = |result: &mut String, paragraph: &mut Vec<(&str, &str, &str)>| {
+        let runs: Vec<&str> = paragraph.iter().map(|&(_, body, _)| body).collect();
+        let converted = convert_block(parser, &runs, BlockKind::Paragraph, target, policy);
+        for (&(prefix, _, newline), body) in paragraph.iter().zip(converted) {
+            result.push_str(prefix);
+            result.push_str(&body);
+            result.push_str(newline);
+        }
+        paragraph.clear();
+    };
+
+    for line in orig.split_inclusive('\n') {
+        let (content, newline) = split_newline(line);
+
+        // フェンスドコードブロックの中はそのまま
+        if let Some(marker) = fence {
+            if content.trim_start().starts_with(marker) {
+                fence = None;
+            }
+            result.push_str(line);
+            continue;
+        }
+
+        let trimmed = content.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            flush(&mut result, &mut paragraph);
+            fence = Some(&trimmed[..3]);
+            result.push_str(line);
+            continue;
+        }
+
+        // 空行と、段落の外のインデントされたコードブロックはそのまま
+        let is_indented = content.starts_with("    ") || content.starts_with('\t');
+        if trimmed.is_empty() || (is_indented && paragraph.is_empty()) {
+            flush(&mut result, &mut paragraph);
+            result.push_str(line);
+            continue;
+        }
+
+        // 引用の `>` とインデントは行頭のマークアップとして扱う
+        let rest = content.trim_start_matches(|c: char| c == '>' || c == ' ' || c == '\t');
+        let (markup, rest) = content.split_at(content.len() - rest.len());
+
+        if is_thematic_break(rest) {
+            flush(&mut result, &mut paragraph);
+            result.push_str(line);
+        } else if let Some(body_start) = heading_marker_len(rest).or_else(|| list_marker_len(rest))
+        {
+            flush(&mut result, &mut paragraph);
+            let kind = if rest.starts_with('#') {
+                BlockKind::Heading
+            } else {
+                BlockKind::ListItem
+            };
+            let (marker, body) = rest.split_at(body_start);
+            let converted = convert_block(parser, &[body], kind, target, policy);
+            result.push_str(markup);
+            result.push_str(marker);
+            result.push_str(&converted[0]);
+            result.push_str(newline);
+        } else if rest.starts_with('|') {
+            flush(&mut result, &mut paragraph);
+            result.push_str(markup);
+            result.push_str(&convert_table_row(parser, rest, target, policy));
+            result.push_str(newline);
+        } else {
+            paragraph.push((markup, rest, newline));
+        }
+    }
+
+    flush(&mut result, &mut paragraph);
+
+    result
+}
+
+fn split_newline(line: &str) -> (&str, &str) {
+    let content = line.trim_end_matches(|c: char| c == '\n' || c == '\r');
+    line.split_at(content.len())
+}
+
+/// `# ` などの見出し記号の長さ。見出しでなければ `None` 。
+fn heading_marker_len(line: &str) -> Option<usize> {
+    let hashes = line.len() - line.trim_start_matches('#').len();
+    if hashes == 0 || hashes > 6 {
+        return None;
+    }
+
+    let rest = &line[hashes..];
+    if rest.is_empty() {
+        return Some(hashes);
+    }
+    if !rest.starts_with(' ') && !rest.starts_with('\t') {
+        return None;
+    }
+
+    Some(line.len() - rest.trim_start().len())
+}
+
+/// `- ` や `1. ` などの箇条書き記号 (とタスクリストのチェックボックス) の長さ。箇条書きでなければ `None` 。
+fn list_marker_len(line: &str) -> Option<usize> {
+    let digits = line.len() - line.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    let marker = if digits == 0 {
+        match line.chars().next() {
+            Some('-') | Some('*') | Some('+') => 1,
+            _ => return None,
+        }
+    } else if line[digits..].starts_with('.') || line[digits..].starts_with(')') {
+        digits + 1
+    } else {
+        return None;
+    };
+
+    let rest = &line[marker..];
+    if !rest.starts_with(' ') && !rest.starts_with('\t') {
+        return None;
+    }
+
+    let rest = rest.trim_start();
+    let rest = ["[ ] ", "[x] ", "[X] "]
+        .iter()
+        .find(|checkbox| rest.starts_with(*checkbox))
+        .map_or(rest, |checkbox| rest[checkbox.len()..].trim_start());
+
+    Some(line.len() - rest.len())
+}
+
+/// `***`, `---`, `___` の区切り線か。記号の間の空白は許す。
+fn is_thematic_break(line: &str) -> bool {
+    let mut marks = line.chars().filter(|c| !c.is_whitespace());
+    let first = match marks.next() {
+        Some(c) if "-*_".contains(c) => c,
+        _ => return false,
+    };
+    let rest: Vec<char> = marks.collect();
+    rest.len() >= 2 && rest.iter().all(|&c| c == first)
+}
+
+fn convert_table_row(
+    parser: &dyn Tokenizer,
+    row: &str,
+    target: Target,
+    policy: &MarkdownPolicy,
+) -> String {
+    let cells: Vec<&str> = row.split('|').collect();
+
+    // 区切り行 (|---|:---:|) はそのまま
+    if cells
+        .iter()
+        .all(|cell| cell.chars().all(|c| "-: \t".contains(c)))
+    {
+        return row.to_string();
+    }
+
+    let converted: Vec<String> = cells
+        .into_iter()
+        .map(|cell| {
+            let body = cell.trim();
+            if body.is_empty() {
+                return cell.to_string();
+            }
+            let leading = cell.len() - cell.trim_start().len();
+            let converted = convert_block(parser, &[body], BlockKind::TableCell, target, policy);
+            format!(
+                "{}{}{}",
+                &cell[..leading],
+                converted[0],
+                &cell[leading + body.len()..]
+            )
+        })
+        .collect();
+
+    converted.join("|")
+}
+
+/// ブロックの本文を方針に従って変換する。本文は複数の断片 (段落の各行など) に分かれていてもよい。
+fn convert_block(
+    parser: &dyn Tokenizer,
+    runs: &[&str],
+    kind: BlockKind,
+    target: Target,
+    policy: &MarkdownPolicy,
+) -> Vec<String> {
+    let text = runs.concat();
+    let edits = block_edits(parser, &text, kind, target, policy);
+
+    // 断片をインラインのコードの境目でさらに分け、コードは書き換えないようにする。(元の断片の番号, 分けた断片)
+    let spans = code_spans(&text);
+    let mut pieces = Vec::new();
+    let mut start = 0;
+    for (idx, run) in runs.iter().enumerate() {
+        let end = start + run.len();
+        let mut pos = start;
+        for span in spans.iter().filter(|s| s.start < end && s.end > start) {
+            let (code_start, code_end) = (span.start.max(start), span.end.min(end));
+            if pos < code_start {
+                pieces.push((idx, &text[pos..code_start], false));
+            }
+            pieces.push((idx, &text[code_start..code_end], true));
+            pos = code_end;
+        }
+        if pos < end || pos == start {
+            pieces.push((idx, &text[pos..end], false));
+        }
+        start = end;
+    }
+
+    let split: Vec<Run> = pieces
+        .iter()
+        .map(|&(_, text, protected)| Run { text, protected })
+        .collect();
+    let mut converted = vec![String::new(); runs.len()];
+    for (&(idx, _, _), piece) in pieces.iter().zip(edit::apply_to_runs(&split, &edits)) {
+        converted[idx].push_str(&piece);
+    }
+    converted
+}
+
+/// インラインのコード (`` `x` ``) の範囲。バッククォートの並びと同じ長さの並びまでをコードとし、閉じていない
+/// ものはただの文字として扱う。
+fn code_spans(text: &str) -> Vec<Range<usize>> {
+    let ticks_at = |pos: usize| text[pos..].len() - text[pos..].trim_start_matches('`').len();
+
+    let mut spans = Vec::new();
+    let mut pos = 0;
+    while let Some(found) = text[pos..].find('`') {
+        let start = pos + found;
+        let ticks = ticks_at(start);
+        pos = start + ticks;
+
+        let mut search = pos;
+        while let Some(found) = text[search..].find('`') {
+            let close = search + found;
+            let len = ticks_at(close);
+            if len == ticks {
+                spans.push(start..close + len);
+                pos = close + len;
+                break;
+            }
+            search = close + len;
+        }
+    }
+
+    spans
+}
+
+/// ブロックの本文に対する置換を方針に従って求める。変換しないブロックなら空。
+pub(crate) fn block_edits(
+    parser: &dyn Tokenizer,
+    text: &str,
+    kind: BlockKind,
+    target: Target,
+    policy: &MarkdownPolicy,
+) -> Vec<Edit> {
+    let should_convert = match policy.get(kind) {
+        BlockPolicy::Convert => true,
+        BlockPolicy::Skip => false,
+        BlockPolicy::SentenceOnly => is_full_sentence(parser, text),
+    };
+
+    if !should_convert {
+        return Vec::new();
+    }
+
+    let mut edits = edit::edits(parser, text, target);
+    if kind != BlockKind::Paragraph {
+        remove_added_period(text, &mut edits);
+    }
+    edits
+}
+
+/// 見出しや箇条書きなどは句点で終わらないのが普通なので、変換で文末に補われた句点を取り除く。
+fn remove_added_period(text: &str, edits: &mut Vec<Edit>) {
+    let end = text.trim_end().len();
+    if text[..end].ends_with(|c| "。．！？".contains(c)) {
+        return;
+    }
+
+    if let Some(last) = edits.last_mut() {
+        if last.range.end == end && last.replacement.ends_with('。') {
+            last.replacement
+                .truncate(last.replacement.len() - '。'.len_utf8());
+        }
+    }
+    edits.retain(|e| text[e.range.clone()] != e.replacement);
+}
+
+/// 記号と終助詞を除いて用言 (動詞・形容詞・助動詞) で終わっていれば文として完結しているとみなす。
+fn is_full_sentence(parser: &dyn Tokenizer, text: &str) -> bool {
+    use crate::tokenizer::{Postpositional as P, WordClass as W};
+
+    parser
+        .tokenize(text)
+        .into_iter()
+        .rev()
+        .find(|m| {
+            !matches!(
+                m.wordclass,
+                W::Symbol(_)
+                    | W::Postpositional(P::End)
+                    | W::Postpositional(P::SupplementaryParallelEnd)
+            )
+        })
+        .is_some_and(|m| matches!(m.wordclass, W::Verb | W::Adjective | W::AuxiliaryVerb))
+}
+
+#[cfg(all(test, feature = "igo"))]
+mod tests {
+    use super::*;
+    use crate::test_parser;
+
+    #[test]
+    fn default_policy() {
+        let orig = "## インストール方法\n\n今日は\n晴天だ。\n\n- 今日は晴天だ。\n- インストール方法\n\n| 項目 | 今日は晴天だ。 |\n|---|---|\n\n```\n今日は晴天だ。\n```\n";
+        let expected = "## インストール方法\n\n今日は\n晴天です。\n\n- 今日は晴天です。\n- インストール方法\n\n| 項目 | 今日は晴天だ。 |\n|---|---|\n\n```\n今日は晴天だ。\n```\n";
+        assert_eq!(
+            to_polite_markdown(test_parser(), orig, &MarkdownPolicy::default()),
+            expected
+        );
+    }
+
+    #[test]
+    fn convert_headings() {
+        let policy = MarkdownPolicy {
+            heading: BlockPolicy::SentenceOnly,
+            ..MarkdownPolicy::default()
+        };
+        assert_eq!(
+            to_polite_markdown(
+                test_parser(),
+                "# 今日は晴天だ\n# インストール方法\n",
+                &policy
+            ),
+            "# 今日は晴天です\n# インストール方法\n"
+        );
+    }
+
+    #[test]
+    fn added_period() {
+        let policy = MarkdownPolicy {
+            heading: BlockPolicy::Convert,
+            list_item: BlockPolicy::Convert,
+            ..MarkdownPolicy::default()
+        };
+        assert_eq!(
+            to_polite_markdown(
+                test_parser(),
+                "# インストール方法\n- 今日は晴天だ\n- 今日は晴天だ。\n\n今日は晴天だ\n",
+                &policy
+            ),
+            "# インストール方法です\n- 今日は晴天です\n- 今日は晴天です。\n\n今日は晴天です。\n"
+        );
+    }
+
+    #[test]
+    fn inline_code() {
+        assert_eq!(
+            to_polite_markdown(
+                test_parser(),
+                "値は`晴天だ`だ。\n値は``a`b``だ。\n",
+                &MarkdownPolicy::default()
+            ),
+            "値は`晴天だ`です。\n値は``a`b``です。\n"
+        );
+        assert_eq!(code_spans("a`b`c``d"), [1..4]);
+    }
+
+    #[test]
+    fn thematic_breaks() {
+        assert!(is_thematic_break("***"));
+        assert!(is_thematic_break("- - -"));
+        assert!(is_thematic_break("____"));
+        assert!(!is_thematic_break("==="));
+        assert!(!is_thematic_break("-*-"));
+        assert!(!is_thematic_break("--"));
+    }
+}
@@ -0,0 +1,210 @@
+//! Python バインディング。
+//!
+//! maturin で `to_polite` モジュールとしてビルドする (`maturin build`。フィーチャーは pyproject.toml で指定して
+//! いる)。辞書の読み込みは最初の呼び出しで一度だけ行う。変換中は GIL を解放するので、Python のスレッドから
+//! 並列に呼べる。
+//!
+//! ```python
+//! import to_polite
+//! to_polite.to_polite("今日は晴天だ。")  # "今日は晴天です。"
+//! to_polite.to_casual("今日は晴天です。", copula_particle="ね")  # "今日は晴天だね。"
+//! to_polite.detect_style("今日は晴天です。")  # "polite"
+//! ```
+
+use crate::casual::Persona;
+use crate::style::{self, Style};
+use crate::{Options, SentenceResult, Target};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use std::sync::OnceLock;
+use typed_igo::Parser;
+
+fn parser() -> &'static Parser {
+    static PARSER: OnceLock<Parser> = OnceLock::new();
+    PARSER.get_or_init(Parser::new)
+}
+
+/// 文ごとの変換結果。位置は Python の文字列の位置 (コードポイント単位)。
+#[pyclass(name = "Sentence", frozen, get_all)]
+pub struct PySentence {
+    start: usize,
+    end: usize,
+    original: String,
+    converted: String,
+    /// 区間ごとに適用した規則の名前
+    rules: Vec<String>,
+    /// "low", "medium", "high" のいずれか
+    confidence: String,
+    needs_review: bool,
+}
+
+#[pymethods]
+impl PySentence {
+    fn __repr__(&self) -> String {
+        format!(
+            "Sentence(start={}, end={}, original={:?}, converted={:?})",
+            self.start, self.end, self.original, self.converted
+        )
+    }
+}
+
+impl PySentence {
+    fn new(orig: &str, result: SentenceResult) -> PySentence {
+        let char_pos = |byte_pos: usize| orig[..byte_pos].chars().count();
+        PySentence {
+            start: char_pos(result.range.start),
+            end: char_pos(result.range.end),
+            original: result.original,
+            converted: result.converted,
+            rules: result
+                .rules
+                .iter()
+                .map(|rule| format!("{:?}", rule))
+                .collect(),
+            confidence: format!("{:?}", result.confidence).to_lowercase(),
+            needs_review: result.needs_review,
+        }
+    }
+}
+
+fn parse_target(target: &str) -> PyResult<Target> {
+    match target {
+        "polite" => Ok(Target::Polite),
+        "impolite" => Ok(Target::Impolite),
+        "casual" => Ok(Target::Casual),
+        "formal" => Ok(Target::Formal),
+        _ => Err(PyValueError::new_err(format!(
+            "target must be \"polite\", \"impolite\", \"casual\" or \"formal\", not {:?}",
+            target
+        ))),
+    }
+}
+
+#[pyfunction]
+fn to_polite(py: Python, text: &str) -> String {
+    py.allow_threads(|| crate::convert_with(parser(), text, &Options::new(Target::Polite)))
+}
+
+#[pyfunction]
+fn to_impolite(py: Python, text: &str) -> String {
+    py.allow_threads(|| crate::convert_with(parser(), text, &Options::new(Target::Impolite)))
+}
+
+#[pyfunction]
+fn to_formal(py: Python, text: &str) -> String {
+    py.allow_threads(|| crate::convert_with(parser(), text, &Options::new(Target::Formal)))
+}
+
+/// 話し言葉に変換する。話し方の項目は `casual::Persona` と同じで、省略したものは既定の値。
+#[pyfunction]
+#[pyo3(signature = (
+    text,
+    copula_particle = "よ",
+    contract_teiru = true,
+    contract_dewa = true,
+    soft_question = true,
+))]
+fn to_casual(
+    py: Python,
+    text: &str,
+    copula_particle: &str,
+    contract_teiru: bool,
+    contract_dewa: bool,
+    soft_question: bool,
+) -> String {
+    let options = Options::new(Target::Casual).persona(Persona {
+        contract_teiru,
+        contract_dewa,
+        copula_particle: copula_particle.to_string(),
+        soft_question,
+    });
+    py.allow_threads(|| crate::convert_with(parser(), text, &options))
+}
+
+/// "polite", "impolite", "mixed", "unknown" のいずれかを返す。
+#[pyfunction]
+fn detect_style(py: Python, text: &str) -> &'static str {
+    match py.allow_threads(|| style::detect_style(parser(), text)) {
+        Style::Polite => "polite",
+        Style::Impolite => "impolite",
+        Style::Mixed => "mixed",
+        Style::Unknown => "unknown",
+    }
+}
+
+/// 文ごとに変換する。`conservative` なら確信度の低い規則が必要な文は変換しない。
+#[pyfunction]
+#[pyo3(signature = (text, target = "polite", conservative = false))]
+fn convert_sentences(
+    py: Python,
+    text: &str,
+    target: &str,
+    conservative: bool,
+) -> PyResult<Vec<PySentence>> {
+    let target = parse_target(target)?;
+    let options = if conservative {
+        Options::conservative(target)
+    } else {
+        Options::new(target)
+    };
+
+    let results = py.allow_threads(|| crate::convert_sentences(parser(), text, &options));
+    Ok(results
+        .into_iter()
+        .map(|result| PySentence::new(text, result))
+        .collect())
+}
+
+#[pymodule]
+#[pyo3(name = "to_polite")]
+fn module(m: &Bound<PyModule>) -> PyResult<()> {
+    m.add_class::<PySentence>()?;
+    m.add_function(wrap_pyfunction!(to_polite, m)?)?;
+    m.add_function(wrap_pyfunction!(to_impolite, m)?)?;
+    m.add_function(wrap_pyfunction!(to_casual, m)?)?;
+    m.add_function(wrap_pyfunction!(to_formal, m)?)?;
+    m.add_function(wrap_pyfunction!(detect_style, m)?)?;
+    m.add_function(wrap_pyfunction!(convert_sentences, m)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn functions() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let m = PyModule::new(py, "to_polite").unwrap();
+            module(&m).unwrap();
+
+            let converted: String = m
+                .getattr("to_polite")
+                .and_then(|f| f.call1(("今日は晴天だ。",)))
+                .and_then(|s| s.extract())
+                .unwrap();
+            assert_eq!(converted, "今日は晴天です。");
+
+            let style: String = m
+                .getattr("detect_style")
+                .and_then(|f| f.call1(("今日は晴天です。",)))
+                .and_then(|s| s.extract())
+                .unwrap();
+            assert_eq!(style, "polite");
+
+            assert_eq!(
+                to_casual(py, "今日は晴天です。", "ね", true, true, true),
+                "今日は晴天だね。"
+            );
+
+            let sentences = convert_sentences(py, "今日は晴天だ。", "polite", false).unwrap();
+            assert_eq!(sentences[0].converted, "今日は晴天です。");
+            assert_eq!(to_formal(py, "ここに本がある。"), "ここに本がございます。");
+
+            let sentences = convert_sentences(py, "今日は晴天だ。", "formal", false).unwrap();
+            assert_eq!(sentences[0].converted, "今日は晴天でございます。");
+            assert!(convert_sentences(py, "", "humble", false).is_err());
+        });
+    }
+}
@@ -0,0 +1,145 @@
+//! 変換結果の読み。
+//!
+//! 音声合成にそのまま渡せるよう、変換後の文の読み (カタカナ) と発音を返す。変換で変わらなかった部分は元の文を
+//! 解析したときの読みをそのまま使い、「ます」「でした」「ありません」などの変換で足したり活用させたりした部分
+//! だけを解析し直して辞書の読みを使う。辞書に読みのない未知語は表記をカタカナにしたものを読みとする。
+
+use crate::edit;
+use crate::tokenizer::{Morpheme, Tokenizer};
+use crate::Target;
+
+/// 文とその読み。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reading {
+    pub text: String,
+    /// 読み (例: 「今日は」 -> 「キョウハ」)
+    pub reading: String,
+    /// 発音 (例: 「今日は」 -> 「キョーワ」)
+    pub pronunciation: String,
+}
+
+pub fn to_polite_with_reading(parser: &dyn Tokenizer, orig: &str) -> Reading {
+    convert_with_reading(parser, orig, Target::Polite)
+}
+
+pub fn to_impolite_with_reading(parser: &dyn Tokenizer, orig: &str) -> Reading {
+    convert_with_reading(parser, orig, Target::Impolite)
+}
+
+pub fn convert_with_reading(parser: &dyn Tokenizer, orig: &str, target: Target) -> Reading {
+    let edits = edit::edits(parser, orig, target);
+    let morphs = parser.tokenize(orig);
+    let mut reading = String::new();
+    let mut pronunciation = String::new();
+    let mut pos = 0;
+
+    for edit in &edits {
+        // 形態素の途中で始まる (終わる) 置換は、その形態素ごと読み直す
+        let containing = |idx: usize| {
+            morphs
+                .iter()
+                .find(|m| m.start < idx && idx < m.start + m.surface.len())
+        };
+        let start = containing(edit.range.start)
+            .map_or(edit.range.start, |m| m.start)
+            .max(pos);
+        let end = containing(edit.range.end)
+            .map_or(edit.range.end, |m| m.start + m.surface.len())
+            .max(start);
+
+        push_readings(
+            morphs
+                .iter()
+                .filter(|m| m.start >= pos && m.start + m.surface.len() <= start),
+            &mut reading,
+            &mut pronunciation,
+        );
+        let replaced = orig[start..edit.range.start.max(start)].to_string()
+            + &edit.replacement
+            + &orig[edit.range.end.max(start)..end];
+        push_readings(
+            &parser.tokenize(&replaced),
+            &mut reading,
+            &mut pronunciation,
+        );
+        pos = end;
+    }
+    push_readings(
+        morphs.iter().filter(|m| m.start >= pos),
+        &mut reading,
+        &mut pronunciation,
+    );
+
+    Reading {
+        text: edit::apply(orig, &edits),
+        reading,
+        pronunciation,
+    }
+}
+
+/// 文の読みと発音を返す。
+pub fn read(parser: &dyn Tokenizer, text: &str) -> (String, String) {
+    let mut reading = String::new();
+    let mut pronunciation = String::new();
+    push_readings(&parser.tokenize(text), &mut reading, &mut pronunciation);
+
+    (reading, pronunciation)
+}
+
+fn push_readings<'a>(
+    morphs: impl IntoIterator<Item = &'a Morpheme>,
+    reading: &mut String,
+    pronunciation: &mut String,
+) {
+    for m in morphs {
+        reading.push_str(&kana_or_surface(&m.reading, &m.surface));
+        pronunciation.push_str(&kana_or_surface(&m.pronunciation, &m.surface));
+    }
+}
+
+fn kana_or_surface(kana: &str, surface: &str) -> String {
+    if kana.is_empty() || kana == "*" {
+        to_katakana(surface)
+    } else {
+        kana.to_string()
+    }
+}
+
+fn to_katakana(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            'ぁ'..='ゖ' => std::char::from_u32(c as u32 + 0x60).unwrap_or(c),
+            _ => c,
+        })
+        .collect()
+}
+
+#[cfg(all(test, feature = "igo"))]
+mod tests {
+    use super::*;
+    use crate::test_parser;
+
+    #[test]
+    fn polite_reading() {
+        assert_eq!(
+            to_polite_with_reading(test_parser(), "今日は晴天だ。"),
+            Reading {
+                text: "今日は晴天です。".to_string(),
+                reading: "キョウハセイテンデス。".to_string(),
+                pronunciation: "キョーワセイテンデス。".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn conjugated_reading() {
+        let reading = to_polite_with_reading(test_parser(), "本を読む。");
+        assert_eq!(reading.text, "本を読みます。");
+        assert_eq!(reading.reading, "ホンヲヨミマス。");
+    }
+
+    #[test]
+    fn katakana() {
+        assert_eq!(to_katakana("ほげクラウド"), "ホゲクラウド");
+    }
+}
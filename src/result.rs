@@ -0,0 +1,259 @@
+//! 構造を持った変換結果。
+//!
+//! 文字列だけでなく、区間 (文、または接続助詞の「が」で区切った部分) ごとに元の文、変換後の文、位置、区切り、
+//! 元の文体、適用した規則を返す。`serde` フィーチャーを有効にすると JSON などに書き出せる。
+
+use crate::casual;
+use crate::edit::{self, Edit};
+use crate::rule::{Confidence, Rule};
+use crate::style::Style;
+use crate::tokenizer::ConjugationForm as F;
+use crate::tokenizer::Tokenizer;
+use crate::{parts, Options, Part, Target};
+use std::ops::Range;
+
+/// 文章全体の変換結果。
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ConversionResult {
+    pub target: Target,
+    /// 変換後の文章
+    pub converted: String,
+    pub sentences: Vec<ConvertedSentence>,
+}
+
+/// 区間ごとの変換結果。
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ConvertedSentence {
+    /// 元の文。区切りは含まない。
+    pub original: String,
+    /// 変換後の文。区切りは含まないが、句点で終わっていない文に補った句点は含む。変換しなかった場合は元の文
+    /// のまま。
+    pub converted: String,
+    /// 元の文章での位置 (バイト単位)。区切りは含まない。
+    pub range: Range<usize>,
+    /// 区切りの句点や接続助詞。文章の末尾などで区切りがなければ `None` 。
+    pub separator: Option<String>,
+    /// 変換後の区切り。話し言葉で「か。」を「の？」にする場合などは `separator` と異なる。
+    pub converted_separator: Option<String>,
+    /// 元の文の文体
+    pub style: Style,
+    /// 適用した (変換しなかった場合は適用するはずだった) 規則
+    pub rule: Rule,
+    pub confidence: Confidence,
+    /// 変換しなかった。変換の必要がない、変換する単語がない (括弧の中だけなど)、確信度が足りない、のいずれか。
+    /// 確信度は `convert_with()` と同じく文ごとに判定するので、同じ文の区間のどれかで足りなければ文全体を変換
+    /// しない。
+    pub skipped: bool,
+}
+
+/// `options` に従って変換し、区間ごとの結果とともに返す。
+pub fn conversion_result(
+    parser: &dyn Tokenizer,
+    orig: &str,
+    options: &Options,
+) -> ConversionResult {
+    let mut sentences = Vec::new();
+    for range in edit::sentence_ranges(orig) {
+        let morphs = parser.tokenize(&orig[range.clone()]);
+        let converted: Vec<_> = parts(&morphs)
+            .into_iter()
+            .map(|part| convert_part(parser, orig, range.start, part, options))
+            .collect();
+
+        let confidence = converted
+            .iter()
+            .map(|sentence| sentence.confidence)
+            .min()
+            .unwrap_or(Confidence::High);
+        let needs_review = options.min_confidence.is_some_and(|min| confidence < min);
+        sentences.extend(converted.into_iter().map(|mut sentence| {
+            if needs_review {
+                sentence.converted = sentence.original.clone();
+                sentence.converted_separator = sentence.separator.clone();
+                sentence.skipped = true;
+            }
+            sentence
+        }));
+    }
+
+    let mut edits = Vec::new();
+    for sentence in &sentences {
+        edits.extend(
+            edit::minimal_edit(&sentence.original, &sentence.converted).map(|edit| Edit {
+                range: sentence.range.start + edit.range.start
+                    ..sentence.range.start + edit.range.end,
+                replacement: edit.replacement,
+            }),
+        );
+
+        // 区切りは区間の後ろにある
+        if sentence.converted_separator != sentence.separator {
+            if let Some(sep) = &sentence.separator {
+                let end = sentence.range.end;
+                if let Some(idx) = orig[end..].find(sep.as_str()) {
+                    edits.push(Edit {
+                        range: end + idx..end + idx + sep.len(),
+                        replacement: sentence.converted_separator.clone().unwrap_or_default(),
+                    });
+                }
+            }
+        }
+    }
+
+    ConversionResult {
+        target: options.target,
+        converted: edit::apply(orig, &edits),
+        sentences,
+    }
+}
+
+/// 確信度にかかわらず変換する。`offset` は区間を含む文の、文章での位置。
+fn convert_part(
+    parser: &dyn Tokenizer,
+    orig: &str,
+    offset: usize,
+    part: Part,
+    options: &Options,
+) -> ConvertedSentence {
+    let target = options.target;
+    let start = part
+        .morphs
+        .first()
+        .or(part.sep.as_ref())
+        .map_or(0, |m| m.start);
+    let end = part
+        .morphs
+        .last()
+        .map_or(start, |m| m.start + m.surface.len());
+    let range = offset + start..offset + end;
+    let separator = part.orig_sep().map(|sep| sep.surface.to_string());
+
+    let style = part.style();
+    let conversion = match target {
+        Target::Polite => part.into_polite(F::Basic),
+        Target::Impolite | Target::Casual => part.into_impolite(&[F::Basic]),
+        Target::Formal => part.into_formal(),
+    };
+    let rule = conversion.rule;
+    let confidence = rule.confidence();
+
+    let original = orig[range.clone()].to_string();
+    let converted = match target {
+        Target::Polite | Target::Impolite | Target::Formal => conversion.into_best(),
+        Target::Casual => casual::casualize(parser, &conversion.into_best(), &options.persona),
+    };
+    let (converted, converted_separator) = split_separator(converted, separator.as_deref());
+
+    ConvertedSentence {
+        skipped: converted == original && converted_separator == separator,
+        original,
+        converted,
+        range,
+        separator,
+        converted_separator,
+        style,
+        rule,
+        confidence,
+    }
+}
+
+/// 区切りの付いた変換結果を、区切りを除いたものと変換後の区切りに分ける。`sep` は元の区切り。
+///
+/// 変換で区切りが変わった (「か。」->「の？」) 場合は、最後の文字を変換後の区切りとする。
+fn split_separator(converted: String, sep: Option<&str>) -> (String, Option<String>) {
+    let sep = match sep {
+        Some(sep) => sep,
+        None => return (converted, None),
+    };
+
+    if let Some(body) = converted.strip_suffix(sep) {
+        return (body.to_string(), Some(sep.to_string()));
+    }
+    match converted.char_indices().next_back() {
+        Some((idx, _)) => (
+            converted[..idx].to_string(),
+            Some(converted[idx..].to_string()),
+        ),
+        None => (converted, None),
+    }
+}
+
+#[cfg(all(test, feature = "igo"))]
+mod tests {
+    use super::*;
+    use crate::test_parser;
+
+    #[test]
+    fn sentences() {
+        let orig = "今日は晴天だが、明日は雨です。";
+        let result = conversion_result(test_parser(), orig, &Options::new(Target::Polite));
+        assert_eq!(
+            result.converted,
+            crate::convert_with(test_parser(), orig, &Options::new(Target::Polite))
+        );
+        assert_eq!(result.sentences.len(), 2);
+
+        let first = &result.sentences[0];
+        assert_eq!(first.original, "今日は晴天だ");
+        assert_eq!(first.converted, "今日は晴天です");
+        assert_eq!(&orig[first.range.clone()], "今日は晴天だ");
+        assert_eq!(first.separator.as_deref(), Some("が"));
+        assert_eq!(first.style, Style::Impolite);
+        assert_eq!(first.rule, Rule::Copula);
+        assert!(!first.skipped);
+
+        let second = &result.sentences[1];
+        assert_eq!(second.original, "、明日は雨です");
+        assert_eq!(second.separator.as_deref(), Some("。"));
+        assert_eq!(second.rule, Rule::AlreadyPolite);
+        assert!(second.skipped);
+    }
+
+    #[test]
+    fn conservative() {
+        // 「た」で終わる区間の規則は確信度が低いので、同じ文の区間はどれも変換しない
+        let orig = "今日は晴天だが、寒かった。明日は雨だ。";
+        let options = Options::conservative(Target::Polite);
+        let result = conversion_result(test_parser(), orig, &options);
+        assert_eq!(
+            result.converted,
+            crate::convert_with(test_parser(), orig, &options)
+        );
+        assert_eq!(result.converted, "今日は晴天だが、寒かった。明日は雨です。");
+        assert!(result.sentences[0].skipped);
+    }
+
+    #[test]
+    fn casual() {
+        let orig = "明日は行きますか。";
+        let options = Options::new(Target::Casual);
+        let result = conversion_result(test_parser(), orig, &options);
+        assert_eq!(result.converted, "明日は行くの？");
+        assert_eq!(
+            result.converted,
+            crate::convert_with(test_parser(), orig, &options)
+        );
+
+        let sentence = &result.sentences[0];
+        assert_eq!(sentence.converted, "明日は行くの");
+        assert_eq!(sentence.separator.as_deref(), Some("。"));
+        assert_eq!(sentence.converted_separator.as_deref(), Some("？"));
+
+        let persona = casual::Persona {
+            copula_particle: "ね".to_string(),
+            ..casual::Persona::default()
+        };
+        let options = Options::new(Target::Casual).persona(persona);
+        let orig = "今日は晴天です。";
+        assert_eq!(
+            conversion_result(test_parser(), orig, &options).converted,
+            "今日は晴天だね。"
+        );
+        assert_eq!(
+            crate::convert_with(test_parser(), orig, &options),
+            "今日は晴天だね。"
+        );
+    }
+}
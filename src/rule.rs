@@ -0,0 +1,128 @@
+//! 変換規則とその確信度。
+//!
+//! 規則は `Part::into_polite()` / `Part::into_impolite()` / `Part::into_formal()` の分岐ごとに一つあり、変換するときに変換結果と一緒に
+//! 返される。
+
+use crate::tokenizer::ConjugationKind as K;
+
+/// 変換規則の確信度。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Confidence {
+    /// 推測に近い。人の確認が必要。
+    Low,
+    /// たいていは正しいが、文脈によっては不自然になる。
+    Medium,
+    /// 文法的に確実。
+    High,
+}
+
+/// 区間 (文、または接続助詞の「が」で区切った部分) に適用される変換規則。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Rule {
+    /// 変換する単語がない
+    Empty,
+
+    // ですます調へ
+    /// 「です」「ます」「ください」 : 変換の必要なし
+    AlreadyPolite,
+    /// 助動詞の「だ」 -> 「です」
+    Copula,
+    /// 動詞 -> 連用形 + 「ます」
+    Verb,
+    /// 一段・ル の動詞。連用形の作り方がわかっていない。
+    IchidanVerb,
+    /// 「である」 -> 「です」
+    Dearu,
+    /// 「ある」 -> 「あります」
+    Aru,
+    /// 「でない」 -> 「ではありません」
+    DeNai,
+    /// 動詞の否定 -> 連用形 + 「ません」
+    VerbNai,
+    /// 形容詞の否定 -> 「ありません」
+    AdjectiveNai,
+    /// それ以外の「ない」 -> 「ありません」
+    OtherNai,
+    /// 動詞 + 「た」 -> 連用形 + 「ました」
+    PastVerb,
+    /// 「だった」 -> 「でした」
+    PastCopula,
+    /// 「であった」 -> 「でした」
+    PastDearu,
+    /// 「なかった」 -> 「ありませんでした」
+    PastNai,
+    /// それ以外の「た」 -> 「たです」
+    PastFallback,
+    /// 「しよう」などの「う」 -> 「しましょう」
+    Volitional,
+    /// 否定の「ん」 -> 「ません」
+    NegativeNn,
+    /// それ以外 -> 「です」を追加
+    AppendDesu,
+
+    // である調へ
+    /// 「だ」「ある」など : 変換の必要なし
+    AlreadyPlain,
+    /// 形容詞 + 「です」 -> 「です」を消す
+    DesuAfterAdjective,
+    /// 「たです」 -> 「です」を消す
+    DesuAfterPast,
+    /// 「です」 -> 「だ」
+    Desu,
+    /// 「ございます」「おります」 -> 「ある」「だ」「いる」など
+    Formal,
+    /// 動詞 + 「ます」 -> 終止形
+    MasuAfterVerb,
+    /// それ以外の「ます」 -> 消す
+    MasuOther,
+    /// 「でしょう」 -> 「だろう」
+    VolitionalDesu,
+    /// 「ましょう」 -> 未然ウ接続 + 「う」
+    VolitionalMasu,
+    /// それ以外の「う」 : そのまま
+    VolitionalOther,
+    /// 「ありません」 -> 「ない」
+    NegativeAru,
+    /// 「ません」 -> 未然形 + 「ない」
+    NegativeMasu,
+    /// それ以外の「ん」 -> 未然形 + 「ない」
+    NegativeOther,
+    /// 「でした」 -> 「だった」
+    PastDesu,
+    /// 「ました」 -> 連用タ接続 + 「た」
+    PastMasu,
+    /// それ以外の「た」 : そのまま
+    PastOther,
+
+    // 改まった形へ
+    /// 「だ」「である」「です」 -> 「でございます」
+    FormalCopula,
+    /// 形容詞 -> ウ音便 + 「ございます」。ウ音便の作り方は推測を含む。
+    FormalAdjective,
+    /// 「ある」「あります」 -> 「ございます」
+    FormalAru,
+    /// 「いる」「います」 -> 「おります」。謙譲語なので主語によっては不自然になる。
+    FormalIru,
+}
+
+impl Rule {
+    pub fn confidence(self) -> Confidence {
+        use Rule::*;
+        match self {
+            IchidanVerb | PastFallback | MasuOther | NegativeOther => Confidence::Low,
+            OtherNai | NegativeNn | AppendDesu | VolitionalOther | PastOther | FormalAdjective
+            | FormalIru => Confidence::Medium,
+            _ => Confidence::High,
+        }
+    }
+}
+
+/// 一段・ル の動詞なら `Rule::IchidanVerb` 、それ以外は `rule` 。
+pub(crate) fn verb_rule(kind: K, rule: Rule) -> Rule {
+    match kind {
+        K::IchidanRu => Rule::IchidanVerb,
+        _ => rule,
+    }
+}
@@ -0,0 +1,297 @@
+//! JSON で変換を提供する HTTP サーバー。
+//!
+//! エンドポイントはすべて `POST` で、本文に JSON を受け取る。
+//!
+//! - `/polite`, `/impolite`, `/casual`, `/formal`: `{"text": ..., "conservative": false, "markdown": false}` を変換して
+//!   `{"text": ...}` を返す。`/casual` では `"persona": {"copula_particle": "ね"}` のように話し方を指定できる
+//!   (項目は `casual::Persona` と同じで、省略したものは既定の値)。
+//! - `/detect`: `{"text": ...}` の文体を `{"style": "polite"}` のように返す。
+//! - `/lint`: `{"text": ..., "target": "polite"}` で目標の文体と異なる文を返す。`target` を省略すると文の数が
+//!   多い方の文体にそろえる。
+//!
+//! 誤りは `{"error": ...}` で返す。位置はすべてバイト単位。
+
+use crate::casual::Persona;
+use crate::lint;
+use crate::markdown::{self, MarkdownPolicy};
+use crate::style;
+use crate::tokenizer::Tokenizer;
+use crate::{Options, Target};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::io::Read;
+use tiny_http::{Header, Method, Request, Response, Server};
+
+/// サーバーの設定。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Config {
+    /// 受け付ける本文の最大の大きさ (バイト)
+    pub max_body_size: usize,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            max_body_size: 1024 * 1024,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConvertRequest {
+    text: String,
+    /// 確信度の高い規則だけを使う
+    #[serde(default)]
+    conservative: bool,
+    /// Markdown として変換する
+    #[serde(default)]
+    markdown: bool,
+    /// 話し言葉の話し方
+    #[serde(default)]
+    persona: Option<Persona>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DetectRequest {
+    text: String,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct LintRequest {
+    text: String,
+    #[serde(default)]
+    target: Option<Target>,
+}
+
+/// `server` に届いた要求を処理し続ける。`server` が `unblock()` されると戻る。
+pub fn serve(server: &Server, parser: &dyn Tokenizer, config: &Config) {
+    for mut request in server.incoming_requests() {
+        let (status, body) = match read_body(&mut request, config) {
+            Ok(body) => handle(parser, request.method(), request.url(), &body),
+            Err(error) => error,
+        };
+
+        let header = Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..])
+            .expect("static header is valid");
+        let response = Response::from_string(body.to_string())
+            .with_status_code(status)
+            .with_header(header);
+        // 応答を返せなくてもクライアントが切断しただけなので続ける
+        let _ = request.respond(response);
+    }
+}
+
+fn read_body(request: &mut Request, config: &Config) -> Result<String, (u16, Value)> {
+    let too_large = || {
+        error(
+            413,
+            format!("request body exceeds {} bytes", config.max_body_size),
+        )
+    };
+    if request
+        .body_length()
+        .is_some_and(|len| len > config.max_body_size)
+    {
+        return Err(too_large());
+    }
+
+    let mut body = Vec::new();
+    request
+        .as_reader()
+        .take(config.max_body_size as u64 + 1)
+        .read_to_end(&mut body)
+        .map_err(|e| error(400, e.to_string()))?;
+    if body.len() > config.max_body_size {
+        return Err(too_large());
+    }
+
+    String::from_utf8(body).map_err(|_| error(400, "request body is not valid UTF-8".to_string()))
+}
+
+/// 要求を処理し、ステータスコードと応答の JSON を返す。
+fn handle(parser: &dyn Tokenizer, method: &Method, url: &str, body: &str) -> (u16, Value) {
+    let path = url.split('?').next().unwrap_or(url);
+    let target = match path {
+        "/polite" => Some(Target::Polite),
+        "/impolite" => Some(Target::Impolite),
+        "/casual" => Some(Target::Casual),
+        "/formal" => Some(Target::Formal),
+        "/detect" | "/lint" => None,
+        _ => return error(404, format!("unknown endpoint: {}", path)),
+    };
+    if *method != Method::Post {
+        return error(405, format!("{} only accepts POST", path));
+    }
+
+    let result = match target {
+        Some(target) => parse(body).and_then(|req| convert(parser, target, req)),
+        None if path == "/detect" => parse(body)
+            .map(|req: DetectRequest| json!({ "style": style::detect_style(parser, &req.text) })),
+        None => parse(body).map(|req| lint(parser, req)),
+    };
+
+    match result {
+        Ok(value) => (200, value),
+        Err(error) => error,
+    }
+}
+
+fn parse<T: DeserializeOwned>(body: &str) -> Result<T, (u16, Value)> {
+    serde_json::from_str(body).map_err(|e| error(400, e.to_string()))
+}
+
+fn convert(
+    parser: &dyn Tokenizer,
+    target: Target,
+    req: ConvertRequest,
+) -> Result<Value, (u16, Value)> {
+    if req.persona.is_some() && target != Target::Casual {
+        return Err(error(
+            400,
+            "persona is only supported by /casual".to_string(),
+        ));
+    }
+
+    let text = match (req.markdown, req.conservative) {
+        (true, true) => {
+            return Err(error(
+                400,
+                "conservative is not supported with markdown".to_string(),
+            ))
+        }
+        (true, false) if req.persona.is_some() => {
+            return Err(error(
+                400,
+                "persona is not supported with markdown".to_string(),
+            ))
+        }
+        (true, false) => {
+            markdown::convert_markdown(parser, &req.text, target, &MarkdownPolicy::default())
+        }
+        (false, conservative) => {
+            let options = if conservative {
+                Options::conservative(target)
+            } else {
+                Options::new(target)
+            };
+            let options = options.persona(req.persona.unwrap_or_default());
+            crate::convert_with(parser, &req.text, &options)
+        }
+    };
+
+    Ok(json!({ "text": text }))
+}
+
+fn lint(parser: &dyn Tokenizer, req: LintRequest) -> Value {
+    let problems: Vec<Value> = match req.target.or_else(|| lint::majority(parser, &req.text)) {
+        Some(target) => lint::lint(parser, &req.text, target)
+            .iter()
+            .map(|problem| {
+                json!({
+                    "start": problem.sentence.start,
+                    "end": problem.sentence.end,
+                    "style": problem.style,
+                    "message": problem.describe(&req.text),
+                    "edit": {
+                        "start": problem.edit.range.start,
+                        "end": problem.edit.range.end,
+                        "replacement": problem.edit.replacement,
+                    },
+                })
+            })
+            .collect(),
+        None => Vec::new(),
+    };
+
+    json!({ "problems": problems })
+}
+
+fn error(status: u16, message: String) -> (u16, Value) {
+    (status, json!({ "error": message }))
+}
+
+#[cfg(all(test, feature = "igo"))]
+mod tests {
+    use super::*;
+    use crate::test_parser;
+    use std::io::Write;
+    use std::net::TcpStream;
+    use std::sync::Arc;
+    use std::thread;
+
+    /// 要求を送り、ステータスコードと応答の本文を返す。
+    fn post(addr: &str, path: &str, body: &str) -> (u16, Value) {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        write!(
+            stream,
+            "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            path,
+            addr,
+            body.len(),
+            body
+        )
+        .unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let status = response[9..12].parse().unwrap();
+        let body = &response[response.find("\r\n\r\n").unwrap() + 4..];
+        (status, serde_json::from_str(body).unwrap())
+    }
+
+    #[test]
+    fn endpoints() {
+        let server = Arc::new(Server::http("127.0.0.1:0").unwrap());
+        let addr = server.server_addr().to_ip().unwrap().to_string();
+        let handle = {
+            let server = Arc::clone(&server);
+            thread::spawn(move || serve(&server, test_parser(), &Config { max_body_size: 256 }))
+        };
+
+        let (status, body) = post(&addr, "/polite", r#"{"text": "今日は晴天だ。"}"#);
+        assert_eq!(status, 200);
+        assert_eq!(body["text"], "今日は晴天です。");
+
+        let (status, body) = post(&addr, "/impolite", r#"{"text": "今日は晴天です。"}"#);
+        assert_eq!(status, 200);
+        assert_eq!(body["text"], "今日は晴天だ。");
+
+        let (status, body) = post(
+            &addr,
+            "/casual",
+            r#"{"text": "今日は晴天です。", "persona": {"copula_particle": "ね"}}"#,
+        );
+        assert_eq!(status, 200);
+        assert_eq!(body["text"], "今日は晴天だね。");
+
+        let (_, body) = post(
+            &addr,
+            "/detect",
+            r#"{"text": "今日は晴天です。寒かった。"}"#,
+        );
+        assert_eq!(body["style"], "mixed");
+
+        let (_, body) = post(
+            &addr,
+            "/lint",
+            r#"{"text": "今日は晴天です。寒かった。", "target": "polite"}"#,
+        );
+        assert_eq!(body["problems"].as_array().unwrap().len(), 1);
+        assert_eq!(body["problems"][0]["edit"]["replacement"], "です");
+
+        let (status, _) = post(&addr, "/polite", r#"{"txt": ""}"#);
+        assert_eq!(status, 400);
+        let (status, _) = post(&addr, "/unknown", "{}");
+        assert_eq!(status, 404);
+        let large = format!(r#"{{"text": "{}"}}"#, "あ".repeat(100));
+        let (status, _) = post(&addr, "/polite", &large);
+        assert_eq!(status, 413);
+
+        server.unblock();
+        handle.join().unwrap();
+    }
+}
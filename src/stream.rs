@@ -0,0 +1,212 @@
+//! ストリームの変換。
+//!
+//! 入力全体を読み込まずに、文の区切りまで読んだところで変換して出力する。バッファに持つのは変換が済んでいない
+//! 最後の文だけなので、巨大なコーパスでもメモリ使用量は文の長さ程度に収まる。
+
+use crate::edit::{self, SentenceScanner};
+use crate::tokenizer::Tokenizer;
+use crate::Target;
+use std::collections::VecDeque;
+use std::io::{self, BufRead, Write};
+use std::ops::Range;
+
+/// 区切りが見つからないままこれを超えた場合は、変換せずにそのまま出力する。
+const DEFAULT_MAX_BUFFER: usize = 1024 * 1024;
+
+/// `reader` から読んで変換し、`writer` へ書き出す。
+pub fn convert_stream<R: BufRead, W: Write>(
+    parser: &dyn Tokenizer,
+    reader: R,
+    mut writer: W,
+    target: Target,
+) -> io::Result<()> {
+    for sentence in Sentences::new(parser, reader, target) {
+        writer.write_all(sentence?.as_bytes())?;
+    }
+
+    writer.flush()
+}
+
+/// 変換した文を一つずつ返すイテレータ。
+///
+/// 各要素は直前の文との間の空白や改行を含むので、すべて連結すると入力全体を変換したものになる。
+pub struct Sentences<'p, R> {
+    parser: &'p dyn Tokenizer,
+    reader: R,
+    target: Target,
+    /// まだ出力していない部分
+    buf: String,
+    /// `buf` のどこまで文の区切りを探したか
+    scanner: SentenceScanner,
+    /// UTF-8 の文字の途中で切れているバイト列
+    partial: Vec<u8>,
+    ready: VecDeque<String>,
+    eof: bool,
+    max_buffer: usize,
+}
+
+impl<'p, R: BufRead> Sentences<'p, R> {
+    pub fn new(parser: &'p dyn Tokenizer, reader: R, target: Target) -> Sentences<'p, R> {
+        Sentences {
+            parser,
+            reader,
+            target,
+            buf: String::new(),
+            scanner: SentenceScanner::default(),
+            partial: Vec::new(),
+            ready: VecDeque::new(),
+            eof: false,
+            max_buffer: DEFAULT_MAX_BUFFER,
+        }
+    }
+
+    /// 一つの文として保持する最大のバイト数を指定する。
+    pub fn max_buffer(mut self, max_buffer: usize) -> Sentences<'p, R> {
+        self.max_buffer = max_buffer;
+        self
+    }
+
+    fn fill(&mut self) -> io::Result<()> {
+        let chunk = match self.reader.fill_buf() {
+            Ok(chunk) => chunk,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => return Ok(()),
+            Err(e) => return Err(e),
+        };
+
+        if chunk.is_empty() {
+            self.eof = true;
+            if !self.partial.is_empty() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "stream did not end with a complete UTF-8 character",
+                ));
+            }
+            return Ok(());
+        }
+
+        let len = chunk.len();
+        self.partial.extend_from_slice(chunk);
+        self.reader.consume(len);
+
+        let valid = match std::str::from_utf8(&self.partial) {
+            Ok(text) => text.len(),
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            Err(e) => return Err(io::Error::new(io::ErrorKind::InvalidData, e)),
+        };
+        let text = std::str::from_utf8(&self.partial[..valid])
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        self.buf.push_str(text);
+        self.partial.drain(..valid);
+
+        Ok(())
+    }
+
+    /// バッファのうち、完結した文を変換して出力待ちにする。
+    ///
+    /// 前回までに走査した部分は `scanner` が覚えているので、新しく読んだ部分だけを走査する。
+    fn split_complete(&mut self) {
+        let mut end = 0;
+        while let Some(range) = self.scanner.next(&self.buf) {
+            self.push_converted(end..range.end);
+            end = range.end;
+        }
+
+        if self.eof {
+            // 区切りのない最後の文と末尾の空白
+            if let Some(range) = self.scanner.finish(&self.buf) {
+                self.push_converted(end..range.end);
+                end = range.end;
+            }
+            if end < self.buf.len() {
+                self.ready.push_back(self.buf[end..].to_string());
+                end = self.buf.len();
+            }
+        } else if end == 0 && self.buf.len() > self.max_buffer {
+            // 区切りが見つからないまま大きくなりすぎたので、途中で切れた文を無理に変換せずそのまま流す
+            self.ready.push_back(self.buf.clone());
+            end = self.buf.len();
+            self.scanner = SentenceScanner::default();
+        }
+
+        self.buf.drain(..end);
+        self.scanner.drain(end);
+    }
+
+    /// バッファの `range` を変換して出力待ちにする。
+    fn push_converted(&mut self, range: Range<usize>) {
+        let chunk = &self.buf[range];
+        let edits = edit::edits(self.parser, chunk, self.target);
+        self.ready.push_back(edit::apply(chunk, &edits));
+    }
+}
+
+impl<R: BufRead> Iterator for Sentences<'_, R> {
+    type Item = io::Result<String>;
+
+    fn next(&mut self) -> Option<io::Result<String>> {
+        loop {
+            if let Some(sentence) = self.ready.pop_front() {
+                return Some(Ok(sentence));
+            }
+
+            if self.eof {
+                return None;
+            }
+
+            if let Err(e) = self.fill() {
+                self.eof = true;
+                return Some(Err(e));
+            }
+            self.split_complete();
+        }
+    }
+}
+
+#[cfg(all(test, feature = "igo"))]
+mod tests {
+    use super::*;
+    use crate::test_parser;
+    use std::io::BufReader;
+
+    #[test]
+    fn sentences() {
+        // 文字の途中で切れるよう、わざと小さなバッファで読む
+        let orig = "今日は晴天だ。今日は寒かった。\n\n今日は勉強をしよう。\n";
+        let reader = BufReader::with_capacity(4, orig.as_bytes());
+        let sentences: Vec<String> = Sentences::new(test_parser(), reader, Target::Polite)
+            .collect::<io::Result<_>>()
+            .unwrap();
+        assert_eq!(
+            sentences,
+            [
+                "今日は晴天です。",
+                "今日は寒かったです。",
+                "\n\n今日は勉強をしましょう。",
+                "\n"
+            ]
+        );
+    }
+
+    #[test]
+    fn parens_across_chunks() {
+        let orig = "明日は「雨だ。」と聞いた。";
+        let reader = BufReader::with_capacity(4, orig.as_bytes());
+        let sentences: Vec<String> = Sentences::new(test_parser(), reader, Target::Polite)
+            .collect::<io::Result<_>>()
+            .unwrap();
+        assert_eq!(sentences, ["明日は「雨だ。」と聞きました。"]);
+    }
+
+    #[test]
+    fn to_writer() {
+        let mut output = Vec::new();
+        convert_stream(
+            test_parser(),
+            "今日は晴天です。\n".as_bytes(),
+            &mut output,
+            Target::Impolite,
+        )
+        .unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "今日は晴天だ。\n");
+    }
+}
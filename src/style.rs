@@ -0,0 +1,77 @@
+//! 文体の判定。
+
+use crate::rule::Rule;
+use crate::tokenizer::ConjugationForm as F;
+use crate::tokenizer::Tokenizer;
+use crate::{parts, Part};
+
+/// 文体。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum Style {
+    /// ですます調
+    Polite,
+    /// である調
+    Impolite,
+    /// ですます調とである調が混ざっている
+    Mixed,
+    /// 体言止めだけなどで判定できない
+    Unknown,
+}
+
+impl Style {
+    fn merge(self, other: Style) -> Style {
+        match (self, other) {
+            (Style::Unknown, style) | (style, Style::Unknown) => style,
+            (a, b) if a == b => a,
+            _ => Style::Mixed,
+        }
+    }
+}
+
+/// 文章の文体を判定する。文ごと (接続助詞の「が」の前後も別に数える) の文体をまとめたもの。
+pub fn detect_style(parser: &dyn Tokenizer, orig: &str) -> Style {
+    parts(&parser.tokenize(orig))
+        .iter()
+        .map(Part::style)
+        .fold(Style::Unknown, Style::merge)
+}
+
+impl Part<'_> {
+    /// である調へ変換したときの規則から判定する。変換の必要がなければである調だが、体言止めは判定できない。
+    pub(crate) fn style(&self) -> Style {
+        match self.clone().into_impolite(&[F::Basic]).rule {
+            Rule::Empty => Style::Unknown,
+            Rule::AlreadyPlain | Rule::VolitionalOther | Rule::NegativeOther | Rule::PastOther => {
+                match self.clone().into_polite(F::Basic).rule {
+                    Rule::Empty | Rule::AppendDesu => Style::Unknown,
+                    _ => Style::Impolite,
+                }
+            }
+            _ => Style::Polite,
+        }
+    }
+}
+
+#[cfg(all(test, feature = "igo"))]
+mod tests {
+    use super::*;
+    use crate::test_parser;
+
+    #[test]
+    fn styles() {
+        let detect = |orig| detect_style(test_parser(), orig);
+        assert_eq!(
+            detect("今日は晴天です。行きません。勉強をしましょう。"),
+            Style::Polite
+        );
+        assert_eq!(detect("今日は晴天だ。寒かった。許さん。"), Style::Impolite);
+        assert_eq!(detect("今日は晴天です。寒かった。"), Style::Mixed);
+        assert_eq!(detect("インストール方法"), Style::Unknown);
+        assert_eq!(detect("インストール方法。今日は晴天だ。"), Style::Impolite);
+    }
+}
@@ -0,0 +1,148 @@
+//! textlint 互換の JSON 出力。
+//!
+//! `textlint --format json` と同じ形で、目標の文体と異なる文を修正案つきで報告する。CI で
+//! `textlint-rule-no-mix-dearu-desumasu` の代わりに使える。textlint と同じく、行は 1 から、`loc` の桁は 0 から、
+//! メッセージの `column` は 1 から数え、桁や `index` 、`range` は UTF-16 での位置で表す。
+
+use crate::lint::{self, Problem};
+use crate::tokenizer::Tokenizer;
+use crate::Target;
+use serde::Serialize;
+
+/// `ruleId` に入れる名前。
+pub const RULE_ID: &str = "to-polite";
+
+/// 一つのファイルの結果。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LintResult {
+    pub file_path: String,
+    pub messages: Vec<Message>,
+}
+
+/// textlint のメッセージ。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Message {
+    /// 常に `"lint"`
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub rule_id: &'static str,
+    pub message: String,
+    pub index: usize,
+    pub line: usize,
+    pub column: usize,
+    pub range: [usize; 2],
+    pub loc: Location,
+    /// 常に 2 (エラー)
+    pub severity: u8,
+    pub fix: Fix,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Location {
+    pub start: LineColumn,
+    pub end: LineColumn,
+}
+
+/// 1 から数えた行と 0 から数えた桁。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct LineColumn {
+    pub line: usize,
+    pub column: usize,
+}
+
+/// 修正案。`range` を `text` で置き換える。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Fix {
+    pub range: [usize; 2],
+    pub text: String,
+}
+
+/// `text` を検査した結果を返す。`target` が `None` なら、文の数が多い方の文体にそろえる。
+pub fn lint_result(
+    parser: &dyn Tokenizer,
+    file_path: &str,
+    text: &str,
+    target: Option<Target>,
+) -> LintResult {
+    let messages = match target.or_else(|| lint::majority(parser, text)) {
+        Some(target) => lint::lint(parser, text, target)
+            .iter()
+            .map(|problem| message(text, problem))
+            .collect(),
+        None => Vec::new(),
+    };
+
+    LintResult {
+        file_path: file_path.to_string(),
+        messages,
+    }
+}
+
+/// textlint の `--format json` と同じ JSON にする。
+pub fn to_json(results: &[LintResult]) -> String {
+    serde_json::to_string(results).expect("lint results are always serializable")
+}
+
+fn message(text: &str, problem: &Problem) -> Message {
+    let start = line_column(text, problem.edit.range.start);
+    let end = line_column(text, problem.edit.range.end);
+    let range = [
+        utf16_index(text, problem.edit.range.start),
+        utf16_index(text, problem.edit.range.end),
+    ];
+
+    Message {
+        kind: "lint",
+        rule_id: RULE_ID,
+        message: problem.describe(text),
+        index: range[0],
+        line: start.line,
+        column: start.column + 1,
+        range,
+        loc: Location { start, end },
+        severity: 2,
+        fix: Fix {
+            range,
+            text: problem.edit.replacement.clone(),
+        },
+    }
+}
+
+fn utf16_index(text: &str, pos: usize) -> usize {
+    text[..pos].encode_utf16().count()
+}
+
+/// バイト位置を 1 から数えた行と 0 から数えた桁 (UTF-16) にする。
+fn line_column(text: &str, pos: usize) -> LineColumn {
+    let line_start = text[..pos].rfind('\n').map_or(0, |idx| idx + 1);
+    LineColumn {
+        line: text[..line_start].matches('\n').count() + 1,
+        column: utf16_index(&text[line_start..], pos - line_start),
+    }
+}
+
+#[cfg(all(test, feature = "igo"))]
+mod tests {
+    use super::*;
+    use crate::test_parser;
+
+    #[test]
+    fn messages() {
+        let text = "今日は晴天です。\n𠮷野家は寒かった。\n明日は雨です。";
+        let result = lint_result(test_parser(), "a.md", text, None);
+        assert_eq!(result.messages.len(), 1);
+
+        let message = &result.messages[0];
+        assert_eq!((message.line, message.column), (2, 10));
+        assert_eq!(message.loc.start, LineColumn { line: 2, column: 9 });
+        assert_eq!(message.range, [18, 18]);
+        assert_eq!(message.fix.text, "です");
+
+        let json = to_json(&[result]);
+        assert!(json.starts_with(
+            r#"[{"filePath":"a.md","messages":[{"type":"lint","ruleId":"to-polite","#
+        ));
+    }
+}
@@ -0,0 +1,180 @@
+//! 形態素解析器の抽象化。
+//!
+//! 変換規則はこのモジュールの `Morpheme` だけを見て判断する。`Tokenizer` を実装すれば igo 以外の形態素解析器
+//! でも変換できる。品詞は変換に必要な粒度まで粗くしたもので、活用の種類と形は `conjugation` で活用させるために
+//! そのクレートのものをそのまま使う。
+//!
+//! typed-igo の `Parser` の実装は `igo` フィーチャー (既定で有効) で使える。
+
+pub use conjugation::{ConjugationForm, ConjugationKind};
+#[cfg(feature = "igo")]
+use typed_igo::Parser;
+
+/// 形態素解析器。
+pub trait Tokenizer {
+    fn tokenize(&self, text: &str) -> Vec<Morpheme>;
+}
+
+impl<T: Tokenizer + ?Sized> Tokenizer for &T {
+    fn tokenize(&self, text: &str) -> Vec<Morpheme> {
+        (**self).tokenize(text)
+    }
+}
+
+/// 形態素。
+#[derive(Debug)]
+pub struct Morpheme {
+    /// 表層形
+    pub surface: String,
+    /// 基本形
+    pub basic: String,
+    /// 読み (カタカナ)。辞書にない場合は空か `*` 。
+    pub reading: String,
+    /// 発音 (カタカナ)。辞書にない場合は空か `*` 。
+    pub pronunciation: String,
+    pub wordclass: WordClass,
+    pub conjugation: Conjugation,
+    /// 解析した文字列での位置 (バイト単位)
+    pub start: usize,
+}
+
+/// 品詞。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WordClass {
+    Noun,
+    Verb,
+    Adjective,
+    AuxiliaryVerb,
+    Postpositional(Postpositional),
+    Symbol(Symbol),
+    Other,
+}
+
+/// 助詞の細分類。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Postpositional {
+    /// 終助詞
+    End,
+    /// 副助詞／並立助詞／終助詞
+    SupplementaryParallelEnd,
+    /// 接続助詞
+    Conjunction,
+    Other,
+}
+
+/// 記号の細分類。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Symbol {
+    /// 句点
+    Period,
+    /// 括弧開
+    OpenParen,
+    /// 括弧閉
+    CloseParen,
+    Other,
+}
+
+/// 活用の種類と形。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Conjugation {
+    pub kind: ConjugationKind,
+    pub form: ConjugationForm,
+}
+
+/// 変換規則が見る形態素。規則はスライスパターンで書いているので、文字列は `&str` で持つ。
+#[derive(Debug, Clone)]
+pub(crate) struct MorphemeRef<'a> {
+    pub surface: &'a str,
+    pub basic: &'a str,
+    pub wordclass: WordClass,
+    pub conjugation: Conjugation,
+    /// 解析した文字列での位置 (バイト単位)
+    pub start: usize,
+}
+
+impl Morpheme {
+    pub(crate) fn view(&self) -> MorphemeRef {
+        MorphemeRef {
+            surface: &self.surface,
+            basic: &self.basic,
+            wordclass: self.wordclass,
+            conjugation: self.conjugation,
+            start: self.start,
+        }
+    }
+}
+
+#[cfg(feature = "igo")]
+impl Tokenizer for Parser {
+    fn tokenize(&self, text: &str) -> Vec<Morpheme> {
+        // 位置はほかの解析器とそろえるため、表層形を探してバイト単位で求める。
+        let mut pos = 0;
+        self.parse(text)
+            .into_iter()
+            .map(|m| {
+                let start = text[pos..].find(m.surface).map_or(pos, |idx| pos + idx);
+                pos = start + m.surface.len();
+
+                Morpheme {
+                    surface: m.surface.to_string(),
+                    basic: m.basic.to_string(),
+                    reading: m.reading.to_string(),
+                    pronunciation: m.pronunciation.to_string(),
+                    wordclass: igo_wordclass(&m.wordclass),
+                    conjugation: Conjugation {
+                        kind: m.conjugation.kind,
+                        form: m.conjugation.form,
+                    },
+                    start,
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(feature = "igo")]
+fn igo_wordclass(wordclass: &typed_igo::WordClass) -> WordClass {
+    use typed_igo::wordclass::{Postpositional as P, Symbol as S};
+    use typed_igo::WordClass as W;
+
+    match wordclass {
+        W::Noun(_) => WordClass::Noun,
+        W::Verb(_) => WordClass::Verb,
+        W::Adjective(_) => WordClass::Adjective,
+        W::AuxiliaryVerb => WordClass::AuxiliaryVerb,
+        W::Postpositional(P::End) => WordClass::Postpositional(Postpositional::End),
+        W::Postpositional(P::SupplementaryParallelEnd) => {
+            WordClass::Postpositional(Postpositional::SupplementaryParallelEnd)
+        }
+        W::Postpositional(P::Conjunction) => WordClass::Postpositional(Postpositional::Conjunction),
+        W::Postpositional(_) => WordClass::Postpositional(Postpositional::Other),
+        W::Symbol(S::Period) => WordClass::Symbol(Symbol::Period),
+        W::Symbol(S::OpenParen) => WordClass::Symbol(Symbol::OpenParen),
+        W::Symbol(S::CloseParen) => WordClass::Symbol(Symbol::CloseParen),
+        W::Symbol(_) => WordClass::Symbol(Symbol::Other),
+        _ => WordClass::Other,
+    }
+}
+
+#[cfg(all(test, feature = "igo"))]
+mod tests {
+    use super::*;
+    use crate::test_parser;
+
+    #[test]
+    fn igo_morphemes() {
+        let text = "今日は晴天だ。";
+        let morphs = test_parser().tokenize(text);
+        for m in &morphs {
+            assert_eq!(&text[m.start..m.start + m.surface.len()], m.surface);
+        }
+
+        let last = &morphs[morphs.len() - 2];
+        assert_eq!(last.basic, "だ");
+        assert_eq!(last.wordclass, WordClass::AuxiliaryVerb);
+        assert_eq!(
+            morphs[morphs.len() - 1].wordclass,
+            WordClass::Symbol(Symbol::Period)
+        );
+    }
+}
@@ -0,0 +1,117 @@
+//! 往復変換の検証。
+//!
+//! ある文を変換してから逆向きに変換して、元の文に戻るかを確かめる。戻らない文は変換規則が足りていない可能性が
+//! 高いので、手持ちのコーパスで規則の穴を探すのに使える。
+
+use crate::edit;
+use crate::tokenizer::Tokenizer;
+use crate::{convert, Target};
+use std::fmt;
+use std::ops::Range;
+
+/// 往復変換で元に戻らなかった文。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoundTripFailure {
+    /// 元の文字列での位置
+    pub range: Range<usize>,
+    pub original: String,
+    /// 最初に変換した向き
+    pub via: Target,
+    pub converted: String,
+    pub round_tripped: String,
+}
+
+impl fmt::Display for RoundTripFailure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}..{}: {} => {} => {}",
+            self.range.start, self.range.end, self.original, self.converted, self.round_tripped
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoundTripReport {
+    /// 検証した文の数
+    pub sentences: usize,
+    pub failures: Vec<RoundTripFailure>,
+}
+
+impl RoundTripReport {
+    pub fn is_ok(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+/// 文ごとに往復変換して、元に戻らなかった文を報告する。
+///
+/// すでにですます調の文はである調を経由して、すでにである調の文はですます調を経由して戻す。どちらでもない
+/// (どちらに変換しても変わる) 文は両方向で確かめる。
+pub fn verify_round_trip(parser: &dyn Tokenizer, orig: &str) -> RoundTripReport {
+    let ranges = edit::sentence_ranges(orig);
+    let sentences = ranges.len();
+    let mut failures = Vec::new();
+
+    for range in ranges {
+        let sentence = &orig[range.clone()];
+        let polite = convert(parser, sentence, Target::Polite);
+        let impolite = convert(parser, sentence, Target::Impolite);
+
+        let mut check = |via: Target, converted: String| {
+            let back = match via {
+                Target::Polite => Target::Impolite,
+                Target::Impolite | Target::Casual | Target::Formal => Target::Polite,
+            };
+            let round_tripped = convert(parser, &converted, back);
+            if round_tripped != sentence {
+                failures.push(RoundTripFailure {
+                    range: range.clone(),
+                    original: sentence.to_string(),
+                    via,
+                    converted,
+                    round_tripped,
+                });
+            }
+        };
+
+        let is_polite = polite == sentence;
+        let is_impolite = impolite == sentence;
+        if !is_polite {
+            check(Target::Polite, polite);
+        }
+        if !is_impolite {
+            check(Target::Impolite, impolite);
+        }
+    }
+
+    RoundTripReport {
+        sentences,
+        failures,
+    }
+}
+
+#[cfg(all(test, feature = "igo"))]
+mod tests {
+    use super::*;
+    use crate::test_parser;
+
+    #[test]
+    fn report() {
+        let report = verify_round_trip(test_parser(), "今日は晴天だ。許さん。今日は晴天です。");
+        assert_eq!(report.sentences, 3);
+
+        let failures: Vec<_> = report
+            .failures
+            .iter()
+            .map(|f| (f.original.as_str(), f.via, f.round_tripped.as_str()))
+            .collect();
+        assert_eq!(
+            failures,
+            [
+                ("許さん。", Target::Polite, "許さない。"),
+                ("許さん。", Target::Impolite, "許しません。"),
+            ]
+        );
+    }
+}
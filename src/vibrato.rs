@@ -0,0 +1,57 @@
+//! vibrato による形態素解析。
+//!
+//! IPADIC の辞書で作った `vibrato::Tokenizer` をそのまま `Tokenizer` として使える。解析のたびに worker を作るので、
+//! 同じ `vibrato::Tokenizer` を複数のスレッドから使ってよい。
+//!
+//! ```ignore
+//! let dictionary = vibrato::Dictionary::read(File::open("ipadic-mecab-2_7_0/system.dic")?)?;
+//! let tokenizer = vibrato::Tokenizer::new(dictionary);
+//! to_polite::to_polite_sentence(&tokenizer, "今日は晴天だ。");
+//! ```
+
+use crate::ipadic;
+use crate::tokenizer::{Morpheme, Tokenizer};
+
+impl Tokenizer for vibrato::Tokenizer {
+    fn tokenize(&self, text: &str) -> Vec<Morpheme> {
+        let mut worker = self.new_worker();
+        worker.reset_sentence(text);
+        worker.tokenize();
+
+        worker
+            .token_iter()
+            .map(|token| {
+                let features: Vec<&str> = token.feature().split(',').collect();
+                ipadic::morpheme(token.surface(), token.range_byte().start, &features)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{to_impolite_sentence, to_polite_sentence};
+    use std::fs::File;
+
+    // 辞書は同梱していないので、展開済みの IPADIC の辞書 (system.dic) のパスを `VIBRATO_DICT` に指定して
+    // `cargo test --features vibrato -- --ignored` で実行する。
+    #[test]
+    #[ignore]
+    fn convert() {
+        let path = std::env::var("VIBRATO_DICT").expect("VIBRATO_DICT is not set");
+        let dictionary =
+            vibrato::Dictionary::read(File::open(path).expect("failed to open dictionary"))
+                .expect("failed to read dictionary");
+        let tokenizer = vibrato::Tokenizer::new(dictionary);
+
+        assert_eq!(
+            to_polite_sentence(&tokenizer, "今日は寒くなかった。"),
+            "今日は寒くありませんでした。"
+        );
+        assert_eq!(
+            to_impolite_sentence(&tokenizer, "今日は勉強をしましょう。"),
+            "今日は勉強をしよう。"
+        );
+    }
+}
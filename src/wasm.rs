@@ -0,0 +1,135 @@
+//! WebAssembly 向けの JavaScript API。
+//!
+//! ブラウザでは igo の辞書を読めないので、vibrato の辞書 (IPADIC から作ったもの) を `Uint8Array` で受け取る。
+//! 置換の位置は JavaScript の文字列にそのまま使えるよう、UTF-16 の位置で返す。igo は使わないので、既定の
+//! フィーチャーを外して `cargo rustc --release --lib --target wasm32-unknown-unknown --no-default-features
+//! --features wasm --crate-type cdylib` でビルドし、`wasm-bindgen` にかける。テストは
+//! `wasm-pack test --node -- --no-default-features --features wasm` などで wasm の実行環境で動かす。
+//!
+//! ```js
+//! const converter = new Converter(new Uint8Array(await (await fetch("system.dic")).arrayBuffer()));
+//! converter.toPolite("今日は晴天だ。"); // "今日は晴天です。"
+//! ```
+
+use crate::edit;
+use crate::Target;
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+pub struct Converter {
+    tokenizer: vibrato::Tokenizer,
+}
+
+/// 置換。`start` と `end` は UTF-16 の位置。
+#[wasm_bindgen]
+pub struct Edit {
+    pub start: u32,
+    pub end: u32,
+    replacement: String,
+}
+
+#[wasm_bindgen]
+impl Edit {
+    #[wasm_bindgen(getter)]
+    pub fn replacement(&self) -> String {
+        self.replacement.clone()
+    }
+}
+
+#[wasm_bindgen]
+impl Converter {
+    /// vibrato の辞書から作る。
+    #[wasm_bindgen(constructor)]
+    pub fn new(dictionary: &[u8]) -> Result<Converter, JsError> {
+        let dictionary = vibrato::Dictionary::read(dictionary)?;
+        Ok(Converter {
+            tokenizer: vibrato::Tokenizer::new(dictionary),
+        })
+    }
+
+    #[wasm_bindgen(js_name = toPolite)]
+    pub fn to_polite(&self, text: &str) -> String {
+        self.convert(text, Target::Polite)
+    }
+
+    #[wasm_bindgen(js_name = toImpolite)]
+    pub fn to_impolite(&self, text: &str) -> String {
+        self.convert(text, Target::Impolite)
+    }
+
+    /// ですます調にするための置換。
+    #[wasm_bindgen(js_name = politeEdits)]
+    pub fn polite_edits(&self, text: &str) -> Vec<Edit> {
+        self.edits(text, Target::Polite)
+    }
+
+    /// である調にするための置換。
+    #[wasm_bindgen(js_name = impoliteEdits)]
+    pub fn impolite_edits(&self, text: &str) -> Vec<Edit> {
+        self.edits(text, Target::Impolite)
+    }
+}
+
+impl Converter {
+    fn convert(&self, text: &str, target: Target) -> String {
+        edit::apply(text, &edit::edits(&self.tokenizer, text, target))
+    }
+
+    fn edits(&self, text: &str, target: Target) -> Vec<Edit> {
+        edit::edits(&self.tokenizer, text, target)
+            .into_iter()
+            .map(|edit| Edit {
+                start: utf16_pos(text, edit.range.start),
+                end: utf16_pos(text, edit.range.end),
+                replacement: edit.replacement,
+            })
+            .collect()
+    }
+}
+
+fn utf16_pos(text: &str, byte_pos: usize) -> u32 {
+    text[..byte_pos].encode_utf16().count() as u32
+}
+
+#[cfg(all(test, target_arch = "wasm32"))]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::*;
+
+    /// テスト用の小さな辞書を作る。
+    fn dictionary() -> Vec<u8> {
+        let lexicon = "今日,0,0,1,名詞,副詞可能,*,*,*,*,今日,キョウ,キョー
+は,0,0,1,助詞,係助詞,*,*,*,*,は,ハ,ワ
+晴天,0,0,1,名詞,一般,*,*,*,*,晴天,セイテン,セイテン
+だ,0,0,1,助動詞,*,*,*,特殊・ダ,基本形,だ,ダ,ダ
+です,0,0,1,助動詞,*,*,*,特殊・デス,基本形,です,デス,デス
+。,0,0,1,記号,句点,*,*,*,*,。,。,。
+";
+        let matrix = "1 1\n0 0 0\n";
+        let char_def = "DEFAULT 0 1 0\n";
+        let unk_def = "DEFAULT,0,0,100,名詞,一般,*,*,*,*,*\n";
+
+        let dictionary = vibrato::SystemDictionaryBuilder::from_readers(
+            lexicon.as_bytes(),
+            matrix.as_bytes(),
+            char_def.as_bytes(),
+            unk_def.as_bytes(),
+        )
+        .unwrap();
+        let mut bytes = Vec::new();
+        dictionary.write(&mut bytes).unwrap();
+        bytes
+    }
+
+    #[wasm_bindgen_test]
+    fn convert() {
+        let converter = Converter::new(&dictionary()).unwrap();
+        assert_eq!(converter.to_polite("今日は晴天だ。"), "今日は晴天です。");
+        assert_eq!(converter.to_impolite("今日は晴天です。"), "今日は晴天だ。");
+
+        let edits = converter.polite_edits("今日は晴天だ。");
+        assert_eq!(edits.len(), 1);
+        assert_eq!((edits[0].start, edits[0].end), (5, 6));
+        assert_eq!(edits[0].replacement(), "です");
+    }
+}
@@ -0,0 +1,141 @@
+//! `tests/corpus/*.tsv` の回帰テスト。
+//!
+//! 書式は各ファイルの先頭のコメントを参照。`cargo test --test corpus -- --nocapture` でタグごとの通過率が見られる。
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use to_polite::tokenizer::Tokenizer;
+use to_polite::{to_impolite_sentence, to_polite_sentence};
+use typed_igo::Parser;
+
+struct Case {
+    location: String,
+    original: String,
+    polite: String,
+    impolite: String,
+    tags: Vec<String>,
+}
+
+fn corpus_files() -> Vec<PathBuf> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("corpus");
+    let mut files: Vec<_> = fs::read_dir(&dir)
+        .unwrap_or_else(|e| panic!("failed to read {}: {}", dir.display(), e))
+        .map(|entry| entry.expect("failed to read directory entry").path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "tsv"))
+        .collect();
+    files.sort();
+    files
+}
+
+fn load(path: &Path) -> Vec<Case> {
+    let content = fs::read_to_string(path)
+        .unwrap_or_else(|e| panic!("failed to read {}: {}", path.display(), e));
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.starts_with('#'))
+        .map(|(idx, line)| {
+            let location = format!("{}:{}", name, idx + 1);
+            let columns: Vec<&str> = line.split('\t').collect();
+            if columns.len() < 2 {
+                panic!("{}: expected at least 2 columns", location);
+            }
+
+            let original = columns[0].to_string();
+            let impolite = match columns.get(2) {
+                Some(impolite) if !impolite.is_empty() => impolite.to_string(),
+                _ => original.clone(),
+            };
+            let tags = columns
+                .get(3)
+                .map(|tags| {
+                    tags.split(',')
+                        .map(str::trim)
+                        .filter(|tag| !tag.is_empty())
+                        .map(str::to_string)
+                        .collect()
+                })
+                .unwrap_or_default();
+
+            Case {
+                location,
+                original,
+                polite: columns[1].to_string(),
+                impolite,
+                tags,
+            }
+        })
+        .collect()
+}
+
+#[test]
+fn corpus() {
+    run(&Parser::new());
+}
+
+#[cfg(feature = "lindera")]
+#[test]
+fn corpus_lindera() {
+    use lindera::dictionary::load_dictionary;
+    use lindera::mode::Mode;
+    use lindera::segmenter::Segmenter;
+
+    let dictionary = load_dictionary("embedded://ipadic").expect("failed to load IPADIC");
+    run(&Segmenter::new(Mode::Normal, dictionary, None));
+}
+
+fn run(parser: &dyn Tokenizer) {
+    // タグ -> (通過数, 総数)
+    let mut stats: BTreeMap<String, (usize, usize)> = BTreeMap::new();
+    let mut failures = Vec::new();
+
+    for case in corpus_files().iter().flat_map(|path| load(path)) {
+        let polite = to_polite_sentence(parser, &case.original);
+        let impolite = to_impolite_sentence(parser, &case.polite);
+        let passed = polite == case.polite && impolite == case.impolite;
+
+        let untagged = ["untagged".to_string()];
+        let tags = if case.tags.is_empty() {
+            &untagged[..]
+        } else {
+            &case.tags[..]
+        };
+        for tag in tags {
+            let entry = stats.entry(tag.clone()).or_insert((0, 0));
+            entry.1 += 1;
+            if passed {
+                entry.0 += 1;
+            }
+        }
+
+        if !passed && !case.tags.iter().any(|tag| tag == "xfail") {
+            failures.push(format!(
+                "{}\n  original: {}\n  polite:   {} (expected {})\n  impolite: {} (expected {})",
+                case.location, case.original, polite, case.polite, impolite, case.impolite
+            ));
+        }
+    }
+
+    println!("{:<20} {:>7} {:>7}", "tag", "passed", "rate");
+    for (tag, (passed, total)) in &stats {
+        println!(
+            "{:<20} {:>3}/{:<3} {:>6.1}%",
+            tag,
+            passed,
+            total,
+            *passed as f64 * 100.0 / *total as f64
+        );
+    }
+
+    assert!(
+        failures.is_empty(),
+        "{} case(s) failed:\n{}",
+        failures.len(),
+        failures.join("\n")
+    );
+}